@@ -0,0 +1,127 @@
+//! 比较 `SpatialIndex` 和逐个计算距离的查找耗时, 运行 `cargo bench --bench spatial`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::{Entity, Vec2};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use vector_survivor::components::Enemy;
+use vector_survivor::spatial::SpatialIndex;
+
+const QUERIES: u32 = 1000;
+// 与开火的目标数量和光环的范围相近
+const NEAREST: usize = 5;
+const RADIUS: f32 = 100.0;
+// 敌人分布在玩家周围的范围
+const FIELD: f32 = 2000.0;
+
+fn average(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..QUERIES {
+        f();
+    }
+    start.elapsed() / QUERIES
+}
+
+fn linear_nearest(enemies: &[(Entity, Vec2)], point: Vec2, k: usize) -> Vec<(Entity, Vec2)> {
+    let mut candidates: Vec<(f32, Entity, Vec2)> = enemies
+        .iter()
+        .map(|(entity, position)| (position.distance_squared(point), *entity, *position))
+        .collect();
+    if k < candidates.len() {
+        candidates.select_nth_unstable_by(k, |l, r| l.0.total_cmp(&r.0));
+        candidates.truncate(k);
+    }
+    candidates.sort_unstable_by(|l, r| l.0.total_cmp(&r.0));
+    candidates
+        .into_iter()
+        .map(|(_, entity, position)| (entity, position))
+        .collect()
+}
+
+fn linear_within(enemies: &[(Entity, Vec2)], point: Vec2, radius: f32) -> usize {
+    enemies
+        .iter()
+        .filter(|(_, position)| position.distance_squared(point) <= radius * radius)
+        .count()
+}
+
+fn main() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    println!(
+        "{:>8} {:>12} {:>12} {:>12}",
+        "enemies", "query", "index", "linear"
+    );
+    for count in [5_000, 10_000] {
+        let enemies: Vec<(Entity, Vec2)> = (0..count)
+            .map(|i| {
+                let position =
+                    Vec2::new(rng.gen_range(-FIELD..FIELD), rng.gen_range(-FIELD..FIELD));
+                (Entity::from_raw(i), position)
+            })
+            .collect();
+        let points: Vec<Vec2> = (0..QUERIES)
+            .map(|_| Vec2::new(rng.gen_range(-FIELD..FIELD), rng.gen_range(-FIELD..FIELD)))
+            .collect();
+
+        // 索引每帧重建一次, 重建的耗时也要算进去
+        let mut index = SpatialIndex::<Enemy>::default();
+        let build = average(|| {
+            index.clear();
+            for (entity, position) in &enemies {
+                index.insert(*entity, *position);
+            }
+        });
+        println!("{:>8} {:>12} {:>12?} {:>12}", count, "build", build, "-");
+
+        let mut points_iter = points.iter().cycle();
+        let nearest_index = average(|| {
+            black_box(index.nearest(*points_iter.next().unwrap(), NEAREST));
+        });
+        let nearest_linear = average(|| {
+            black_box(linear_nearest(
+                &enemies,
+                *points_iter.next().unwrap(),
+                NEAREST,
+            ));
+        });
+        println!(
+            "{:>8} {:>12} {:>12?} {:>12?}",
+            count, "nearest", nearest_index, nearest_linear
+        );
+
+        let within_index = average(|| {
+            black_box(index.within(*points_iter.next().unwrap(), RADIUS).count());
+        });
+        let within_linear = average(|| {
+            black_box(linear_within(
+                &enemies,
+                *points_iter.next().unwrap(),
+                RADIUS,
+            ));
+        });
+        println!(
+            "{:>8} {:>12} {:>12?} {:>12?}",
+            count, "within", within_index, within_linear
+        );
+
+        // 两种方式的结果必须一致
+        for point in &points {
+            let distances = |found: Vec<(Entity, Vec2)>| {
+                found
+                    .iter()
+                    .map(|(_, position)| position.distance(*point))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                distances(index.nearest(*point, NEAREST)),
+                distances(linear_nearest(&enemies, *point, NEAREST))
+            );
+            assert_eq!(
+                index.within(*point, RADIUS).count(),
+                linear_within(&enemies, *point, RADIUS)
+            );
+        }
+    }
+}
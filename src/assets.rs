@@ -1,16 +1,31 @@
 use bevy::prelude::*;
+use bevy::time::Stopwatch;
 use bevy_asset_loader::prelude::AssetCollection;
-use std::time::Duration;
 
-#[derive(Debug, Clone, Resource)]
-pub struct Rng(pub rand::rngs::SmallRng);
+use crate::characters::Characters;
+use crate::enemies::Enemies;
+use crate::items::Items;
+use crate::upgrades::Upgrades;
+use crate::waves::Waves;
+use crate::weapons::Weapons;
 
-#[derive(Debug, Clone, Resource)]
-pub struct GameTime(pub Duration);
+/// 本局的游戏时长, 只在 `AppState::InGame` 时前进, 暂停和选择升级的时间不计入
+#[derive(Debug, Clone, Default, Resource)]
+pub struct RunClock(pub Stopwatch);
 
 #[derive(Debug, Clone, Resource)]
 pub struct Killed(pub u32);
 
+/// 游戏世界中可见区域的大小, 有窗口时与主窗口同步
+#[derive(Debug, Clone, Resource)]
+pub struct Viewport(pub Vec2);
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport(Vec2::new(1280f32, 720f32))
+    }
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct FontAssets {
     #[asset(path = "fonts/ZCOOLKuaiLe-Regular.ttf")]
@@ -24,3 +39,39 @@ pub struct AudioAssets {
     #[asset(path = "audio/Hills-of-Radiant-Wind.ogg")]
     pub bgm: Handle<AudioSource>,
 }
+
+#[derive(AssetCollection, Resource)]
+pub struct WeaponAssets {
+    #[asset(path = "data/default.weapons.toml")]
+    pub weapons: Handle<Weapons>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct EnemyAssets {
+    #[asset(path = "data/default.enemies.toml")]
+    pub enemies: Handle<Enemies>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct WaveAssets {
+    #[asset(path = "data/default.waves.toml")]
+    pub waves: Handle<Waves>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct UpgradeAssets {
+    #[asset(path = "data/default.upgrades.toml")]
+    pub upgrades: Handle<Upgrades>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct CharacterAssets {
+    #[asset(path = "data/default.characters.toml")]
+    pub characters: Handle<Characters>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ItemAssets {
+    #[asset(path = "data/default.items.toml")]
+    pub items: Handle<Items>,
+}
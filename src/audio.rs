@@ -0,0 +1,67 @@
+use bevy::audio::{Volume, VolumeLevel};
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::LoadingStateAppExt;
+
+use crate::assets::AudioAssets;
+use crate::components::BGM;
+use crate::gameplay::GameSet;
+use crate::settings::{step_volume, Settings};
+use crate::states::AppState;
+
+/// 背景音乐和音量控制, 音量来自 `Settings`
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .add_systems(OnEnter(AppState::Title), play_bgm)
+            .add_systems(OnEnter(AppState::Tutorial), play_bgm)
+            .add_systems(OnEnter(AppState::Start), play_bgm)
+            .add_systems(Update, volume.in_set(GameSet::Input))
+            .add_systems(
+                Update,
+                apply_volume
+                    .in_set(GameSet::Presentation)
+                    .run_if(resource_changed::<Settings>()),
+            )
+            .add_collection_to_loading_state::<_, AudioAssets>(AppState::Loading);
+    }
+}
+
+/// 还没有播放时开始播放背景音乐, 音乐音量为 0 时也会播放, 之后调高音量就能听到
+pub fn play_bgm(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    playing: Query<(), With<BGM>>,
+) {
+    if !playing.is_empty() {
+        return;
+    }
+    commands.spawn((
+        AudioBundle {
+            source: audio_assets.bgm.clone(),
+            settings: PlaybackSettings::LOOP
+                .with_volume(Volume::Relative(VolumeLevel::new(settings.music_level()))),
+        },
+        BGM,
+    ));
+}
+
+/// `,` 和 `.` 调整音乐音量
+pub fn volume(keyboard_input: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    let change = if keyboard_input.just_pressed(KeyCode::Period) {
+        0.1
+    } else if keyboard_input.just_pressed(KeyCode::Comma) {
+        -0.1
+    } else {
+        return;
+    };
+    step_volume(&mut settings.music_volume, change);
+}
+
+pub fn apply_volume(settings: Res<Settings>, music_controller: Query<&AudioSink, With<BGM>>) {
+    if let Ok(sink) = music_controller.get_single() {
+        sink.set_volume(settings.music_level());
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::assets::CharacterAssets;
+use crate::settings::Language;
+use crate::stats::{Modifier, ModifierSource, Stats};
+use crate::upgrades::UpgradeEffect;
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Characters {
+    #[serde(rename = "character")]
+    pub characters: Vec<Character>,
+}
+
+impl Characters {
+    /// 选中的角色, 没有选择或者找不到时为第一个角色
+    pub fn get_or_default(&self, id: Option<&str>) -> Option<&Character> {
+        id.and_then(|id| self.characters.iter().find(|character| character.id == id))
+            .or_else(|| self.characters.first())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Character {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    pub description: String,
+    #[serde(default)]
+    pub description_en: Option<String>,
+    // 初始武器, 为空时使用 `Weapons::starting`
+    #[serde(default)]
+    pub weapon: Option<String>,
+    #[serde(default)]
+    pub effects: Vec<UpgradeEffect>,
+}
+
+impl Character {
+    pub fn localized_name(&self, language: Language) -> &str {
+        language.pick_data(&self.name, self.name_en.as_deref())
+    }
+
+    pub fn localized_description(&self, language: Language) -> &str {
+        language.pick_data(&self.description, self.description_en.as_deref())
+    }
+
+    pub fn apply(&self, stats: &mut Stats) {
+        for effect in &self.effects {
+            stats.add_modifier(Modifier::new(
+                effect.stat,
+                effect.op,
+                effect.value,
+                ModifierSource::Character(self.id.clone()),
+            ));
+        }
+    }
+}
+
+/// 标题画面中选择的角色
+#[derive(Resource, Debug, Default, Clone)]
+pub struct SelectedCharacter(pub Option<String>);
+
+/// 加载好的角色定义和当前选择的角色
+#[derive(SystemParam)]
+pub struct CharacterSelection<'w> {
+    selected: Res<'w, SelectedCharacter>,
+    assets: Res<'w, CharacterAssets>,
+    characters: Res<'w, Assets<Characters>>,
+}
+
+impl CharacterSelection<'_> {
+    pub fn all(&self) -> &[Character] {
+        self.characters
+            .get(&self.assets.characters)
+            .map_or(&[], |characters| &characters.characters)
+    }
+
+    pub fn get(&self) -> Option<&Character> {
+        self.characters
+            .get(&self.assets.characters)
+            .and_then(|characters| characters.get_or_default(self.selected.0.as_deref()))
+    }
+}
@@ -1,4 +1,8 @@
-use bevy::prelude::{Component, Entity};
+use std::time::Duration;
+
+use bevy::prelude::{Component, Entity, Timer};
+
+use crate::upgrades::Offer;
 
 #[derive(Component)]
 pub struct GameEntity;
@@ -34,7 +38,153 @@ pub struct MoveSpeed(pub f32);
 pub struct AttackTarget(pub Entity);
 
 #[derive(Component)]
-pub struct UIButton(pub &'static str);
+pub struct Damage(pub f32);
+
+// 玩家持有的一把武器, slot 为获得的顺序
+#[derive(Component)]
+pub struct Weapon {
+    pub id: String,
+    pub slot: usize,
+}
+
+#[derive(Component)]
+pub struct WeaponLevel(pub u32);
+
+#[derive(Component)]
+pub struct LastFire(pub Duration);
+
+#[derive(Component)]
+pub struct Health(pub f32);
+
+#[derive(Component)]
+pub struct MaxHealth(pub f32);
+
+#[derive(Component)]
+pub struct ContactDamage(pub f32);
+
+#[derive(Component)]
+pub struct XpReward(pub usize);
+
+// 错开每个敌人移动节奏的时间偏移, 生成时从随机数流中取得, 回放时保持一致
+#[derive(Component)]
+pub struct MovePhase(pub f32);
+
+#[derive(Component)]
+pub struct XpGem(pub usize);
+
+#[derive(Component)]
+pub struct Magnetized;
+
+#[derive(Component)]
+pub struct PickupRadius(pub f32);
+
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+#[derive(Component)]
+pub struct HitFeedback(pub Timer);
+
+// 伤害数字向上飘起并逐渐消失
+#[derive(Component)]
+pub struct DamageNumber(pub Timer);
+
+#[derive(Component)]
+pub struct BulletDamage(pub f32);
+
+#[derive(Component)]
+pub struct BulletLifetime(pub Timer);
+
+// 剩余的飞行距离
+#[derive(Component)]
+pub struct BulletRange(pub f32);
+
+// 目标死亡后追踪最近的敌人
+#[derive(Component)]
+pub struct Retarget;
+
+// 发射这颗子弹的武器
+#[derive(Component)]
+pub struct SourceWeapon(pub String);
+
+// 每秒最多转向的弧度, 没有则直线飞行
+#[derive(Component)]
+pub struct Homing(pub f32);
+
+// 剩余的穿透次数
+#[derive(Component)]
+pub struct Pierce(pub u32);
+
+#[derive(Component)]
+pub struct Ricochet {
+    pub remaining: u32,
+    pub range: f32,
+}
+
+#[derive(Component)]
+pub struct Split {
+    pub count: u32,
+    pub damage: f32,
+}
+
+// 已经命中过的敌人, 穿透和弹射时不会重复命中
+#[derive(Component, Default)]
+pub struct HitEnemies(pub Vec<Entity>);
+
+// 第几把环绕刀刃
+#[derive(Component)]
+pub struct OrbitBlade(pub usize);
+
+// 刀刃命中过的敌人和再次造成伤害前剩余的秒数
+#[derive(Component, Default)]
+pub struct BladeHits(pub Vec<(Entity, f32)>);
+
+// 光环结算伤害的计时器
+#[derive(Component)]
+pub struct Aura(pub Timer);
+
+// 发出冲击波的武器, 和 `LastFire` 一起记录上次发出的时间
+#[derive(Component)]
+pub struct ShockwaveEmitter;
+
+#[derive(Component)]
+pub struct Shockwave {
+    pub timer: Timer,
+    pub radius: f32,
+    pub damage: f32,
+}
+
+#[derive(Component)]
+pub enum UIButton {
+    Restart,
+    Title,
+    Start,
+    SelectUpgrade(Offer),
+    SkipUpgrade,
+    Reroll,
+    Banish,
+}
+
+/// 暂停菜单中的按钮, 回放时也可以点击
+#[derive(Component)]
+pub enum PauseButton {
+    Resume,
+    Settings,
+    Back,
+    QuitToTitle,
+}
+
+/// 设置页面中的按钮, 标题画面和暂停菜单共用
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    MasterVolume(f32),
+    MusicVolume(f32),
+    SfxVolume(f32),
+    Fullscreen,
+    Vsync,
+    ScreenShake,
+    DamageNumbers,
+    Language,
+}
 
 #[derive(Component)]
 pub struct GameOverUI;
@@ -45,6 +195,24 @@ pub struct SelectUpgradeUI;
 #[derive(Component)]
 pub struct TutorialUI;
 
+#[derive(Component)]
+pub struct PauseUI;
+
+#[derive(Component)]
+pub struct TitleUI;
+
+#[derive(Component)]
+pub enum TitleButton {
+    Characters,
+    Character(String),
+    Tutorial,
+    Settings,
+    Statistics,
+    Credits,
+    Back,
+    Quit,
+}
+
 #[derive(Component)]
 pub struct XPBar;
 
@@ -60,5 +228,8 @@ pub struct StatsUIKill;
 #[derive(Component)]
 pub struct StatsUITime;
 
+#[derive(Component)]
+pub struct WeaponStrip;
+
 #[derive(Component)]
 pub struct BGM;
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Enemies {
+    #[serde(rename = "enemy")]
+    pub archetypes: Vec<EnemyArchetype>,
+}
+
+impl Enemies {
+    pub fn get(&self, id: &str) -> Option<&EnemyArchetype> {
+        self.archetypes.iter().find(|archetype| archetype.id == id)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyArchetype {
+    pub id: String,
+    pub shape: EnemyShape,
+    pub color: String,
+    pub size: f32,
+    pub health: f32,
+    pub speed: SpeedRange,
+    pub contact_damage: f32,
+    pub xp: usize,
+    #[serde(default)]
+    pub behaviour: Behaviour,
+}
+
+impl EnemyArchetype {
+    pub fn color(&self) -> Color {
+        Color::hex(&self.color).unwrap_or(Color::RED)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SpeedRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyShape {
+    Square,
+    Circle,
+    Triangle,
+}
+
+#[derive(Component, Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    // 直线追向玩家
+    #[default]
+    Chase,
+    // 左右摇摆着接近玩家
+    Zigzag,
+    // 停顿后突然冲刺
+    Dash,
+}
@@ -1,91 +1,91 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
 use crate::assets::Killed;
-use crate::components::{
-    Bullet, BulletSpeed, FireRate, MoveSpeed, Player, StatsUIKill, TargetCount,
-};
+use crate::components::StatsUIKill;
+use crate::upgrades::Offer;
 
 #[derive(Event)]
-pub struct XpIncEvent;
+pub struct XpIncEvent(pub usize);
 
 #[derive(Event)]
-pub struct KillEvent;
+pub struct KillEvent {
+    pub xp: usize,
+    pub position: Vec2,
+}
 
+// 敌人受到的实际伤害, 用于显示伤害数字
 #[derive(Event)]
-pub struct PlayerMoveSpeedUpEvent;
+pub struct DamageEvent {
+    pub position: Vec2,
+    pub damage: f32,
+}
 
+/// 伤害敌人时需要发送的事件
+#[derive(SystemParam)]
+pub struct DamageEvents<'w> {
+    pub damage: EventWriter<'w, DamageEvent>,
+    pub kill: EventWriter<'w, KillEvent>,
+}
+
+// 子弹消失时分裂成多颗
 #[derive(Event)]
-pub struct PlayerFireRateUpEvent;
+pub struct BulletSplitEvent {
+    pub weapon: String,
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub count: u32,
+    pub damage: f32,
+    pub speed: f32,
+    // 母弹已经命中过的敌人, 分裂出的子弹不会再打它们
+    pub hit: Vec<Entity>,
+}
 
 #[derive(Event)]
-pub struct PlayerBulletSpeedUpEvent;
+pub struct UpgradeSelectedEvent(pub Offer);
 
 #[derive(Event)]
-pub struct PlayerTargetCountUpEvent;
+pub struct SkipUpgradeEvent;
 
 #[derive(Event)]
-pub struct PropsUpdateEvent;
+pub struct RerollUpgradesEvent;
 
 #[derive(Event)]
-pub struct StartEvent(pub bool);
+pub struct BanishUpgradeEvent(pub Offer);
 
-pub fn read_player_move_speed_up_event(
-    mut ev_player_move_speed_up: EventReader<PlayerMoveSpeedUpEvent>,
-    mut speed: Query<&mut MoveSpeed, (With<Player>, Without<Bullet>)>,
-    mut ev_props_update: EventWriter<PropsUpdateEvent>,
-) {
-    let mut speed = speed.single_mut();
-    for _ in ev_player_move_speed_up.read() {
-        speed.0 *= 1.05;
-        ev_props_update.send(PropsUpdateEvent);
-    }
+/// 发出升级界面上的操作, 来自按钮或者录像回放
+#[derive(SystemParam)]
+pub struct UpgradeActions<'w> {
+    pub selected: EventWriter<'w, UpgradeSelectedEvent>,
+    pub skip: EventWriter<'w, SkipUpgradeEvent>,
+    pub reroll: EventWriter<'w, RerollUpgradesEvent>,
+    pub banish: EventWriter<'w, BanishUpgradeEvent>,
 }
 
-pub fn read_player_fire_rate_up_event(
-    mut ev_player_fire_rate_up: EventReader<PlayerFireRateUpEvent>,
-    mut rate: Query<&mut FireRate, With<Player>>,
-    mut ev_props_update: EventWriter<PropsUpdateEvent>,
-) {
-    let mut rate = rate.single_mut();
-    for _ in ev_player_fire_rate_up.read() {
-        rate.0 *= 1.05;
-        ev_props_update.send(PropsUpdateEvent);
-    }
+/// 读取升级界面上的操作
+#[derive(SystemParam)]
+pub struct UpgradeActionEvents<'w, 's> {
+    pub selected: EventReader<'w, 's, UpgradeSelectedEvent>,
+    pub skip: EventReader<'w, 's, SkipUpgradeEvent>,
+    pub reroll: EventReader<'w, 's, RerollUpgradesEvent>,
+    pub banish: EventReader<'w, 's, BanishUpgradeEvent>,
 }
 
-pub fn read_player_bullet_speed_up_event(
-    mut ev_player_bullet_speed_up: EventReader<PlayerBulletSpeedUpEvent>,
-    mut speed: Query<&mut BulletSpeed, With<Player>>,
-    mut ev_props_update: EventWriter<PropsUpdateEvent>,
-) {
-    let mut speed = speed.single_mut();
-    for _ in ev_player_bullet_speed_up.read() {
-        speed.0 *= 1.05;
-        ev_props_update.send(PropsUpdateEvent);
-    }
-}
+#[derive(Event)]
+pub struct PropsUpdateEvent;
 
-pub fn read_player_target_count_up_event(
-    mut ev_player_target_count_up: EventReader<PlayerTargetCountUpEvent>,
-    mut count: Query<&mut TargetCount, With<Player>>,
-    mut ev_props_update: EventWriter<PropsUpdateEvent>,
-) {
-    let mut count = count.single_mut();
-    for _ in ev_player_target_count_up.read() {
-        count.0 += 1;
-        ev_props_update.send(PropsUpdateEvent);
-    }
-}
+#[derive(Event)]
+pub struct StartEvent;
 
 pub fn read_kill_event(
     mut ev_kill: EventReader<KillEvent>,
-    mut ev_xp_up: EventWriter<XpIncEvent>,
     mut killed: ResMut<Killed>,
     mut stat: Query<&mut Text, With<StatsUIKill>>,
 ) {
     for _ in ev_kill.read() {
         killed.0 += 1;
-        stat.single_mut().sections[0].value = format!("killed {}", killed.0);
-        ev_xp_up.send(XpIncEvent);
+        if let Ok(mut stat) = stat.get_single_mut() {
+            stat.sections[0].value = format!("killed {}", killed.0);
+        }
     }
 }
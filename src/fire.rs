@@ -1,59 +1,246 @@
 use std::time::Duration;
 
-use bevy::prelude::{Color, Commands, Entity, Local, Query, Res, Transform, With, Without};
-use bevy::time::Time;
-use bevy_vector_shapes::prelude::{DiscBundle, ShapeBundle, ShapeConfig};
+use bevy::ecs::system::{EntityCommands, SystemParam};
+use bevy::prelude::{
+    Commands, Entity, EventReader, Quat, Query, Res, ResMut, Timer, TimerMode, Transform, Vec2,
+    With, Without,
+};
+use bevy_vector_shapes::prelude::{ShapeBundle, ShapeConfig};
 use bevy_xpbd_2d::prelude::Collider;
+use rand::seq::SliceRandom;
 
+use crate::assets::RunClock;
 use crate::components::{
-    AttackTarget, Bullet, BulletSpeed, Enemy, FireRate, GameEntity, MoveSpeed, Player, TargetCount,
+    AttackTarget, Bullet, BulletDamage, BulletLifetime, BulletRange, BulletSpeed, Damage, Enemy,
+    FireRate, GameEntity, HitEnemies, Homing, LastFire, MoveSpeed, Pierce, Player, Retarget,
+    Ricochet, SourceWeapon, Split, TargetCount, Weapon,
 };
+use crate::events::BulletSplitEvent;
+use crate::rng::RngStreams;
+use crate::spatial::SpatialIndex;
+use crate::stats::{Stat, Stats};
+use crate::weapons::{ProjectileShape, Targeting, WeaponDef, WeaponDefs};
+
+// 分裂子弹散开的总角度
+const SPLIT_SPREAD: f32 = std::f32::consts::FRAC_PI_2;
+
+/// 按武器的索敌方式挑选目标
+#[derive(SystemParam)]
+pub struct TargetFinder<'w, 's> {
+    enemies: Query<'w, 's, (&'static Transform, Entity), (With<Enemy>, Without<Player>)>,
+    index: Res<'w, SpatialIndex<Enemy>>,
+    rng: ResMut<'w, RngStreams>,
+}
+
+impl TargetFinder<'_, '_> {
+    pub fn is_empty(&self) -> bool {
+        self.enemies.is_empty()
+    }
+
+    pub fn find(
+        &mut self,
+        position: Vec2,
+        targeting: Targeting,
+        count: usize,
+    ) -> Vec<(Entity, Vec2)> {
+        match targeting {
+            Targeting::Nearest => self.index.nearest(position, count),
+            Targeting::Farthest => {
+                let mut entities: Vec<(f32, Entity, Vec2)> = self
+                    .enemies
+                    .iter()
+                    .map(|(transform, entity)| {
+                        let target = transform.translation.truncate();
+                        (position.distance_squared(target), entity, target)
+                    })
+                    .collect();
+                // 只挑出最远的几个再排序, 不用对所有敌人排序
+                let count = count.min(entities.len());
+                if count > 0 && count < entities.len() {
+                    entities.select_nth_unstable_by(count - 1, |l, r| r.0.total_cmp(&l.0));
+                    entities.truncate(count);
+                }
+                entities.sort_unstable_by(|l, r| r.0.total_cmp(&l.0));
+                entities
+                    .into_iter()
+                    .map(|(_, entity, target)| (entity, target))
+                    .collect()
+            }
+            Targeting::Random => {
+                let mut entities: Vec<(Entity, Vec2)> = self
+                    .enemies
+                    .iter()
+                    .map(|(transform, entity)| (entity, transform.translation.truncate()))
+                    .collect();
+                // 只打乱需要的数量
+                let count = count.min(entities.len());
+                entities
+                    .partial_shuffle(&mut self.rng.combat, count)
+                    .0
+                    .to_vec()
+            }
+        }
+    }
+}
 
 pub fn player_fire(
     mut commands: Commands,
-    players: Query<
-        (&Transform, &FireRate, &TargetCount, &BulletSpeed),
-        (With<Player>, Without<Enemy>),
-    >,
-    enemies: Query<(&Transform, Entity), (With<Enemy>, Without<Player>)>,
-    time: Res<Time>,
-    mut last_fire: Local<Duration>,
+    players: Query<(&Transform, &Stats), (With<Player>, Without<Enemy>)>,
+    mut inventory: Query<(
+        &Weapon,
+        &FireRate,
+        &TargetCount,
+        &BulletSpeed,
+        &BulletDamage,
+        &mut LastFire,
+    )>,
+    mut targets: TargetFinder,
+    clock: Res<RunClock>,
+    weapons: WeaponDefs,
 ) {
-    if enemies.is_empty() {
+    if targets.is_empty() {
         return;
     }
-    let (player, fire_rate, target_count, bullet_speed) = players.get_single().unwrap();
-    // pre minute
-    let rate = Duration::from_secs(60).as_millis() as f32 / fire_rate.0;
-    let interval = time.elapsed() - *last_fire;
-
-    // fire
-    if interval.as_millis() >= rate as u128 {
-        let mut entities: Vec<(f32, Entity)> = enemies
-            .iter()
-            .map(|enemy| (player.translation.distance(enemy.0.translation), enemy.1))
-            .collect();
-        glidesort::sort_in_vec_by(&mut entities, |l, r| l.0.total_cmp(&r.0));
-
-        let target = entities.iter().take(target_count.0);
-
-        for (_, entity) in target {
-            commands.spawn((
-                ShapeBundle::circle(
-                    &ShapeConfig {
-                        color: Color::WHITE,
-                        transform: Transform::from_translation(player.translation),
-                        ..ShapeConfig::default_2d()
-                    },
-                    3.0,
-                ),
-                GameEntity,
-                Bullet,
-                MoveSpeed(bullet_speed.0),
-                AttackTarget(*entity),
-                Collider::ball(3.0),
+    let (player, stats) = players.get_single().unwrap();
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    let position = player.translation.truncate();
+    for (equipped, fire_rate, target_count, bullet_speed, bullet_damage, mut last_fire) in
+        &mut inventory
+    {
+        let Some(weapon) = weapons.get(&equipped.id) else {
+            continue;
+        };
+        // pre minute
+        let rate = Duration::from_secs(60).as_millis() as f32 / fire_rate.0;
+        let interval = clock.0.elapsed().saturating_sub(last_fire.0);
+        if interval.as_millis() < rate as u128 {
+            continue;
+        }
+
+        let found = targets.find(position, weapon.targeting, target_count.0);
+        for (entity, target) in found.into_iter().take(target_count.0) {
+            let mut bullet = spawn_bullet(
+                &mut commands,
+                weapon,
+                stats,
+                position,
+                target - position,
+                bullet_speed.0,
+                bullet_damage.0,
+            );
+            bullet.insert(AttackTarget(entity));
+            let projectile = &weapon.projectile;
+            if projectile.homing > 0f32 {
+                bullet.insert(Homing(projectile.homing));
+            }
+            if weapon.retarget {
+                bullet.insert(Retarget);
+            }
+            if projectile.pierce > 0 {
+                bullet.insert(Pierce(projectile.pierce));
+            }
+            if projectile.ricochet > 0 {
+                bullet.insert(Ricochet {
+                    remaining: projectile.ricochet,
+                    range: projectile.ricochet_range,
+                });
+            }
+            if projectile.split > 0 {
+                bullet.insert(Split {
+                    count: projectile.split,
+                    damage: projectile.split_damage,
+                });
+            }
+        }
+        last_fire.0 = clock.0.elapsed();
+    }
+}
+
+/// 子弹消失时向前方扇形分裂出直线飞行的子弹
+pub fn split_bullets(
+    mut commands: Commands,
+    mut ev_split: EventReader<BulletSplitEvent>,
+    players: Query<&Stats, With<Player>>,
+    weapons: WeaponDefs,
+) {
+    let (Some(weapons), Ok(stats)) = (weapons.get(), players.get_single()) else {
+        return;
+    };
+    for split in ev_split.read() {
+        let Some(weapon) = weapons.get(&split.weapon) else {
+            continue;
+        };
+        let spread = SPLIT_SPREAD / split.count.max(1) as f32;
+        for i in 0..split.count {
+            let angle = (i as f32 - (split.count - 1) as f32 / 2f32) * spread;
+            let direction = Vec2::from_angle(angle).rotate(split.direction);
+            spawn_bullet(
+                &mut commands,
+                weapon,
+                stats,
+                split.position,
+                direction,
+                split.speed,
+                split.damage,
+            )
+            .insert((
+                AttackTarget(Entity::PLACEHOLDER),
+                HitEnemies(split.hit.clone()),
             ));
         }
-        *last_fire = time.elapsed();
     }
 }
+
+fn spawn_bullet<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    weapon: &WeaponDef,
+    stats: &Stats,
+    position: Vec2,
+    direction: Vec2,
+    speed: f32,
+    damage: f32,
+) -> EntityCommands<'w, 's, 'a> {
+    let duration = stats.get(Stat::Duration);
+    let radius = weapon.radius * stats.get(Stat::Area);
+    let mut bullet = commands.spawn((
+        GameEntity,
+        Bullet,
+        MoveSpeed(speed),
+        Damage(damage),
+        BulletLifetime(Timer::from_seconds(
+            weapon.lifetime * duration,
+            TimerMode::Once,
+        )),
+        BulletRange(weapon.range * duration),
+        HitEnemies::default(),
+        SourceWeapon(weapon.id.clone()),
+        Collider::ball(radius),
+    ));
+    let transform = Transform::from_translation(position.extend(0f32)).with_rotation(
+        Quat::from_rotation_arc_2d(Vec2::Y, direction.try_normalize().unwrap_or(Vec2::Y)),
+    );
+    insert_projectile_shape(&mut bullet, weapon, radius, transform);
+    bullet
+}
+
+fn insert_projectile_shape(
+    bullet: &mut EntityCommands,
+    weapon: &WeaponDef,
+    radius: f32,
+    transform: Transform,
+) {
+    let config = ShapeConfig {
+        color: weapon.color(),
+        transform,
+        ..ShapeConfig::default_2d()
+    };
+    match weapon.shape {
+        ProjectileShape::Circle => bullet.insert(ShapeBundle::circle(&config, radius)),
+        ProjectileShape::Square => {
+            bullet.insert(ShapeBundle::rect(&config, Vec2::splat(radius * 2f32)))
+        }
+        ProjectileShape::Triangle => bullet.insert(ShapeBundle::ngon(&config, 3f32, radius)),
+    };
+}
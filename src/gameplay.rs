@@ -0,0 +1,418 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::{LoadingState, LoadingStateAppExt};
+use bevy_common_assets::toml::TomlAssetPlugin;
+use bevy_vector_shapes::prelude::{Rectangle, ShapeBundle, ShapeConfig};
+use bevy_xpbd_2d::prelude::{Collider, Gravity, PhysicsPlugins, RigidBody};
+use rand::Rng;
+
+use crate::assets::{
+    CharacterAssets, EnemyAssets, ItemAssets, Killed, RunClock, UpgradeAssets, Viewport,
+    WaveAssets, WeaponAssets,
+};
+use crate::characters::{CharacterSelection, Characters, SelectedCharacter};
+use crate::components::{
+    ContactDamage, Enemy, GameEntity, Health, Level, MovePhase, MoveSpeed, Player, XPBar, XpGem,
+    XpReward, XP,
+};
+use crate::enemies::{Enemies, EnemyShape};
+use crate::events::{
+    read_kill_event, BanishUpgradeEvent, BulletSplitEvent, DamageEvent, KillEvent,
+    PropsUpdateEvent, RerollUpgradesEvent, SkipUpgradeEvent, UpgradeSelectedEvent, XpIncEvent,
+};
+use crate::fire::{player_fire, split_bullets};
+use crate::input::{read_keyboard_input, PlayerInput};
+use crate::inventory::{apply_weapon_offer, apply_weapon_stats, spawn_weapon};
+use crate::items::{apply_item_offer, Items, OwnedItems};
+use crate::melee::{expand_shockwaves, fire_shockwave, update_aura, update_orbit_blades};
+use crate::movement::{
+    bullet_collision, enemy_approaches_player, expire_bullets, move_bullet, move_player,
+    tick_hit_feedback, tick_invulnerable,
+};
+use crate::pickups::{attract_xp_gems, drop_xp_gems};
+use crate::rng::{RngStreams, RunSeed};
+use crate::spatial::{update_spatial_index, SpatialIndex};
+use crate::states::AppState;
+use crate::stats::{apply_stats, regenerate_health, tick_stat_modifiers, Stat, Stats};
+use crate::title::TitlePlugin;
+use crate::upgrades::{
+    apply_upgrade, handle_upgrade_offers, roll_upgrade_offers, AcquiredUpgrades, UpgradeOffers,
+    Upgrades,
+};
+use crate::waves::{WaveDirector, Waves};
+use crate::weapons::{WeaponDefs, Weapons};
+
+/// 每帧 `Update` 中按顺序执行的阶段, 物理模拟在之后的 `PostUpdate` 中进行
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    // 读取玩家输入
+    Input,
+    // 生成敌人和敌人行为
+    Ai,
+    // 子弹和经验宝石的移动
+    Movement,
+    // 开火, 碰撞和伤害
+    Combat,
+    // 经验, 升级和属性
+    Progression,
+    // 界面和画面反馈
+    Presentation,
+}
+
+/// 不依赖窗口和渲染的游戏逻辑: 状态, 数据资源, 物理和战斗.
+/// 其他插件会往 `AppState::Loading` 里添加资源, 所以需要最先添加.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            TomlAssetPlugin::<Weapons>::new(&["weapons.toml"]),
+            TomlAssetPlugin::<Enemies>::new(&["enemies.toml"]),
+            TomlAssetPlugin::<Waves>::new(&["waves.toml"]),
+            TomlAssetPlugin::<Upgrades>::new(&["upgrades.toml"]),
+            TomlAssetPlugin::<Items>::new(&["items.toml"]),
+            TomlAssetPlugin::<Characters>::new(&["characters.toml"]),
+        ))
+        .add_plugins(
+            // 物理在 Update 之后运行, 本帧所有 GameSet 的结果都会在同一帧内被模拟
+            PhysicsPlugins::new(PostUpdate),
+        )
+        .add_event::<XpIncEvent>()
+        .add_event::<KillEvent>()
+        .add_event::<DamageEvent>()
+        .add_event::<BulletSplitEvent>()
+        .add_event::<UpgradeSelectedEvent>()
+        .add_event::<SkipUpgradeEvent>()
+        .add_event::<RerollUpgradesEvent>()
+        .add_event::<BanishUpgradeEvent>()
+        .add_event::<PropsUpdateEvent>()
+        // states
+        .add_state::<AppState>()
+        .add_loading_state(LoadingState::new(AppState::Loading).continue_to_state(AppState::Title))
+        .add_systems(OnEnter(AppState::Start), setup_game)
+        .add_systems(OnEnter(AppState::SelectUpgrade), roll_upgrade_offers)
+        .configure_sets(
+            Update,
+            (
+                GameSet::Input,
+                GameSet::Ai,
+                GameSet::Movement,
+                GameSet::Combat,
+                GameSet::Progression,
+                GameSet::Presentation,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            handle_upgrade_offers
+                .in_set(GameSet::Progression)
+                .run_if(in_state(AppState::SelectUpgrade)),
+        )
+        .add_systems(OnEnter(AppState::GameOver), exit_game)
+        // 从暂停菜单回到标题时还有这一局的实体
+        .add_systems(OnEnter(AppState::Title), exit_game)
+        .add_systems(
+            Update,
+            (
+                (tick_run_clock, read_keyboard_input, move_player)
+                    .chain()
+                    .in_set(GameSet::Input),
+                //move_player_with_touch,
+                //move_player_with_mouse,
+                (spawn_enemy, enemy_approaches_player)
+                    .chain()
+                    .in_set(GameSet::Ai),
+                (
+                    (update_spatial_index::<Enemy>, update_spatial_index::<XpGem>),
+                    (move_bullet, attract_xp_gems),
+                    expire_bullets,
+                )
+                    .chain()
+                    .in_set(GameSet::Movement),
+                (
+                    player_fire,
+                    bullet_collision,
+                    split_bullets,
+                    update_orbit_blades,
+                    update_aura,
+                    fire_shockwave,
+                    expand_shockwaves,
+                    (tick_invulnerable, tick_hit_feedback),
+                )
+                    .chain()
+                    .in_set(GameSet::Combat),
+                (
+                    (read_kill_event, drop_xp_gems, render_xp_bar),
+                    (
+                        (apply_upgrade, apply_weapon_offer, apply_item_offer),
+                        tick_stat_modifiers,
+                        (apply_stats, apply_weapon_stats),
+                        regenerate_health,
+                    )
+                        .chain(),
+                )
+                    .in_set(GameSet::Progression),
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        //.add_systems(OnExit(AppState::InGame), exit_game)
+        .insert_resource(Gravity(Vec2::ZERO))
+        .init_resource::<RunClock>()
+        .insert_resource(Killed(0))
+        .init_resource::<Viewport>()
+        .init_resource::<PlayerInput>()
+        .init_resource::<SpatialIndex<Enemy>>()
+        .init_resource::<SpatialIndex<XpGem>>()
+        .init_resource::<WaveDirector>()
+        .init_resource::<UpgradeOffers>()
+        .init_resource::<RunSeed>()
+        .init_resource::<SelectedCharacter>()
+        .init_resource::<RngStreams>()
+        .add_collection_to_loading_state::<_, WeaponAssets>(AppState::Loading)
+        .add_collection_to_loading_state::<_, EnemyAssets>(AppState::Loading)
+        .add_collection_to_loading_state::<_, WaveAssets>(AppState::Loading)
+        .add_collection_to_loading_state::<_, UpgradeAssets>(AppState::Loading)
+        .add_collection_to_loading_state::<_, ItemAssets>(AppState::Loading)
+        .add_collection_to_loading_state::<_, CharacterAssets>(AppState::Loading);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // 没有标题画面时加载完成直接开始游戏
+        if !app.is_plugin_added::<TitlePlugin>() {
+            app.add_systems(OnEnter(AppState::Title), skip_title.after(exit_game));
+        }
+    }
+}
+
+fn skip_title(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Start);
+}
+
+/// 每局开始时需要重置的资源
+#[derive(SystemParam)]
+pub struct RunState<'w> {
+    clock: ResMut<'w, RunClock>,
+    killed: ResMut<'w, Killed>,
+    director: ResMut<'w, WaveDirector>,
+    offers: ResMut<'w, UpgradeOffers>,
+    seed: ResMut<'w, RunSeed>,
+    rng: ResMut<'w, RngStreams>,
+}
+
+impl RunState<'_> {
+    pub fn reset(&mut self) {
+        self.clock.0.reset();
+        self.killed.0 = 0;
+        *self.director = WaveDirector::default();
+        self.seed.current = self.seed.fixed.unwrap_or_else(rand::random);
+        *self.rng = RngStreams::new(self.seed.current);
+        info!("run seed: {}", self.seed.current);
+        *self.offers = UpgradeOffers::default();
+    }
+}
+
+pub fn setup_game(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut run: RunState,
+    characters: CharacterSelection,
+    weapons: WeaponDefs,
+) {
+    let weapons = weapons
+        .get()
+        .expect("weapons asset is loaded before the game starts");
+    let character = characters.get();
+    let starting = character
+        .and_then(|character| character.weapon.as_ref())
+        .unwrap_or(&weapons.starting);
+    let weapon = weapons
+        .get(starting)
+        .unwrap_or_else(|| panic!("unknown starting weapon `{}`", starting));
+
+    let mut stats = Stats::new([
+        (Stat::MaxHealth, 100.0),
+        (Stat::MoveSpeed, 100.0),
+        (Stat::FireRate, 1.0),
+        (Stat::BulletSpeed, 1.0),
+        (Stat::BulletDamage, 1.0),
+        (Stat::TargetCount, 0.0),
+        (Stat::PickupRadius, 50.0),
+        (Stat::Cooldown, 1.0),
+        (Stat::Area, 1.0),
+        (Stat::Duration, 1.0),
+        (Stat::Luck, 1.0),
+    ]);
+    if let Some(character) = character {
+        info!("playing as {}", character.id);
+        character.apply(&mut stats);
+    }
+
+    // spawn player
+    commands.spawn((
+        ShapeBundle::circle(
+            &ShapeConfig {
+                color: Color::PINK,
+                ..ShapeConfig::default_2d()
+            },
+            8.0,
+        ),
+        GameEntity,
+        Player,
+        (
+            XP(0),
+            Level(1),
+            AcquiredUpgrades::default(),
+            OwnedItems::default(),
+        ),
+        Health(stats.get(Stat::MaxHealth)),
+        stats.components(),
+        stats,
+        RigidBody::Dynamic,
+        Collider::ball(8.0),
+    ));
+    spawn_weapon(&mut commands, weapon, 0);
+    commands.spawn((
+        ShapeBundle::rect(
+            &ShapeConfig {
+                color: Color::GREEN,
+                ..ShapeConfig::default_2d()
+            },
+            Vec2::new(50f32, 5f32),
+        ),
+        GameEntity,
+        XPBar,
+    ));
+    run.reset();
+    next_state.set(AppState::InGame);
+}
+
+pub fn tick_run_clock(time: Res<Time>, mut clock: ResMut<RunClock>) {
+    clock.0.tick(time.delta());
+}
+
+pub fn exit_game(mut commands: Commands, entities: Query<Entity, With<GameEntity>>) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// 生成敌人需要的波次进度, 随机数和敌人定义
+#[derive(SystemParam)]
+pub struct EnemySpawner<'w> {
+    director: ResMut<'w, WaveDirector>,
+    rng: ResMut<'w, RngStreams>,
+    enemy_assets: Res<'w, EnemyAssets>,
+    enemies: Res<'w, Assets<Enemies>>,
+    wave_assets: Res<'w, WaveAssets>,
+    waves: Res<'w, Assets<Waves>>,
+}
+
+pub fn spawn_enemy(
+    mut commands: Commands,
+    time: Res<Time>,
+    clock: Res<RunClock>,
+    viewport: Res<Viewport>,
+    player: Query<&Transform, With<Player>>,
+    alive: Query<(), With<Enemy>>,
+    mut spawner: EnemySpawner,
+) {
+    let player = player.single();
+    let (Some(enemies), Some(waves)) = (
+        spawner.enemies.get(&spawner.enemy_assets.enemies),
+        spawner.waves.get(&spawner.wave_assets.waves),
+    ) else {
+        return;
+    };
+
+    let count = spawner
+        .director
+        .tick(time.delta(), clock.0.elapsed(), waves);
+    let Some(wave) = spawner.director.current(waves) else {
+        return;
+    };
+    let count = (count as usize).min(wave.max_alive.saturating_sub(alive.iter().count()));
+    let rng = &mut spawner.rng.spawning;
+
+    for _ in 0..count {
+        let Some(archetype) = wave.pick(rng).and_then(|id| enemies.get(id)) else {
+            continue;
+        };
+
+        let x1 = player.translation.x + viewport.0.x / 2f32;
+        let x2 = player.translation.x - viewport.0.x / 2f32;
+        let y1 = player.translation.y + viewport.0.y / 2f32;
+        let y2 = player.translation.y - viewport.0.y / 2f32;
+
+        let random_point = if rng.gen_bool(0.5) {
+            // 在屏幕上下出现
+            let x = rng.gen_range(x2..=x1);
+            let y = if rng.gen_bool(0.5) { y1 } else { y2 };
+            Vec2::new(x, y)
+        } else {
+            // 在屏幕左右出现
+            let y = rng.gen_range(y2..=y1);
+            let x = if rng.gen_bool(0.5) { x1 } else { x2 };
+            Vec2::new(x, y)
+        };
+
+        let config = ShapeConfig {
+            color: archetype.color(),
+            transform: Transform::from_translation(random_point.extend(0f32)),
+            ..ShapeConfig::default_2d()
+        };
+        let mut enemy = commands.spawn((
+            GameEntity,
+            Enemy,
+            archetype.behaviour,
+            ContactDamage(archetype.contact_damage),
+            Health(archetype.health),
+            XpReward(archetype.xp),
+            MoveSpeed(rng.gen_range(archetype.speed.min..=archetype.speed.max)),
+            MovePhase(rng.gen_range(0f32..6f32)),
+            RigidBody::Dynamic,
+            Collider::ball(archetype.size / 2f32),
+        ));
+        match archetype.shape {
+            EnemyShape::Square => {
+                enemy.insert(ShapeBundle::rect(&config, Vec2::splat(archetype.size)))
+            }
+            EnemyShape::Circle => enemy.insert(ShapeBundle::circle(&config, archetype.size / 2f32)),
+            EnemyShape::Triangle => {
+                enemy.insert(ShapeBundle::ngon(&config, 3f32, archetype.size / 2f32))
+            }
+        };
+    }
+}
+
+pub fn render_xp_bar(
+    mut ev_xp_inc: EventReader<XpIncEvent>,
+    mut bar: Query<&mut Rectangle, With<XPBar>>,
+    mut xp: Query<&mut XP>,
+    mut level: Query<&mut Level>,
+    viewport: Res<Viewport>,
+    mut offers: ResMut<UpgradeOffers>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    const BASE_REQUIRED_FOR_UPGRADE: usize = 12;
+
+    for inc in ev_xp_inc.read() {
+        let mut lvl = level.single_mut();
+        let mut xp = xp.single_mut();
+        xp.0 += inc.0;
+        // 一次获得的经验可能连升几级, 每升一级都要选择一次升级
+        let mut required_for_upgrade = BASE_REQUIRED_FOR_UPGRADE + lvl.0 * 3;
+        while xp.0 >= required_for_upgrade {
+            xp.0 -= required_for_upgrade;
+            lvl.0 += 1;
+            offers.pending += 1;
+            required_for_upgrade = BASE_REQUIRED_FOR_UPGRADE + lvl.0 * 3;
+        }
+
+        bar.single_mut().size.x = xp.0 as f32 * (viewport.0.x / required_for_upgrade as f32) * 2f32;
+    }
+    // 选完一次后回到游戏让升级生效, 下一帧再选下一次
+    if offers.pending > 0 {
+        next_state.set(AppState::SelectUpgrade);
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::assets::{Killed, RunClock};
+use crate::components::{Level, Player};
+use crate::events::{SkipUpgradeEvent, UpgradeSelectedEvent};
+use crate::gameplay::GameSet;
+use crate::replay::ReplayPlayback;
+use crate::rng::RunSeed;
+use crate::states::AppState;
+use crate::upgrades::UpgradeOffers;
+
+// 每次更新固定推进的时间, 与60帧的窗口模式保持一致
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// 不打开窗口也不渲染, 只运行游戏逻辑.
+/// 时间按固定步长推进, 升级时自动选择第一个选项, 需要和 `GameplayPlugin` 一起使用.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_TIME))
+        .add_systems(
+            Update,
+            auto_select_upgrade
+                .in_set(GameSet::Input)
+                .run_if(in_state(AppState::SelectUpgrade))
+                .run_if(not(resource_exists::<ReplayPlayback>())),
+        )
+        .add_systems(OnEnter(AppState::GameOver), report_run);
+    }
+}
+
+fn auto_select_upgrade(
+    offers: Res<UpgradeOffers>,
+    mut ev_upgrade_selected: EventWriter<UpgradeSelectedEvent>,
+    mut ev_skip: EventWriter<SkipUpgradeEvent>,
+) {
+    match offers.offers.first() {
+        Some(offer) => ev_upgrade_selected.send(UpgradeSelectedEvent(offer.clone())),
+        None => ev_skip.send(SkipUpgradeEvent),
+    }
+}
+
+/// 一局结束时报告的结果, 游戏结束后插入
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct RunReport {
+    pub seed: u64,
+    pub survived: Duration,
+    pub killed: u32,
+    pub level: usize,
+}
+
+fn report_run(
+    mut commands: Commands,
+    clock: Res<RunClock>,
+    killed: Res<Killed>,
+    seed: Res<RunSeed>,
+    level: Query<&Level, With<Player>>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    let report = RunReport {
+        seed: seed.current,
+        survived: clock.0.elapsed(),
+        killed: killed.0,
+        level: level.get_single().map_or(0, |level| level.0),
+    };
+    info!(
+        "game over: seed {}, survived {:?}, killed {}, level {}",
+        report.seed, report.survived, report.killed, report.level,
+    );
+    commands.insert_resource(report);
+    ev_exit.send(AppExit);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::utils::HashMap;
+
+    use super::*;
+    use crate::assets::Viewport;
+    use crate::components::{Bullet, BulletLifetime};
+    use crate::gameplay::GameplayPlugin;
+    use crate::replay::{Replay, ReplayPlugin, ReplayRecorder};
+
+    const SEED: u64 = 42;
+    // 资源在后台线程加载, 按实际时间限制等待
+    const LOADING_TIMEOUT: Duration = Duration::from_secs(60);
+    // 每帧 1/60 秒, 大约两分钟
+    const FRAMES: usize = 7200;
+    // 同时存在的子弹数量上限
+    const MAX_BULLETS: usize = 1000;
+
+    fn app() -> App {
+        app_with(|_| {})
+    }
+
+    /// `setup` 在资源加载之前运行, 用来添加其他插件
+    fn app_with(setup: impl FnOnce(&mut App)) -> App {
+        let mut app = App::new();
+        app.add_plugins((HeadlessPlugin, GameplayPlugin))
+            .insert_resource(RunSeed::fixed(SEED));
+        setup(&mut app);
+        let start = Instant::now();
+        while *app.world.resource::<State<AppState>>() == AppState::Loading {
+            assert!(
+                start.elapsed() < LOADING_TIMEOUT,
+                "assets did not load within {:?}",
+                LOADING_TIMEOUT
+            );
+            app.update();
+        }
+        app
+    }
+
+    /// 跑到游戏结束或者 `FRAMES` 帧, 没有结束时用当前的数据生成报告
+    fn run(app: &mut App) -> RunReport {
+        for _ in 0..FRAMES {
+            app.update();
+            if let Some(report) = app.world.get_resource::<RunReport>() {
+                return report.clone();
+            }
+        }
+        let level = app
+            .world
+            .query_filtered::<&Level, With<Player>>()
+            .single(&app.world)
+            .0;
+        RunReport {
+            seed: app.world.resource::<RunSeed>().current,
+            survived: app.world.resource::<RunClock>().0.elapsed(),
+            killed: app.world.resource::<Killed>().0,
+            level,
+        }
+    }
+
+    #[test]
+    fn headless_run() {
+        let report = run(&mut app());
+        assert_eq!(report.seed, SEED);
+        assert!(report.survived > Duration::from_secs(10), "{:?}", report);
+        assert!(report.killed > 0, "{:?}", report);
+        assert!(report.level > 1, "{:?}", report);
+
+        // 同一个种子的结果完全相同
+        assert_eq!(run(&mut app()), report);
+    }
+
+    #[test]
+    fn bullets_do_not_leak() {
+        let mut app = app();
+        let mut bullets = app
+            .world
+            .query_filtered::<(Entity, &BulletLifetime), With<Bullet>>();
+        // 每颗子弹第一次出现时的时间, 只计算游戏进行中的帧
+        let mut fired = HashMap::new();
+        let mut now = Duration::ZERO;
+        for _ in 0..FRAMES {
+            app.update();
+            if app.world.contains_resource::<RunReport>() {
+                break;
+            }
+            if *app.world.resource::<State<AppState>>() == AppState::InGame {
+                now += FRAME_TIME;
+            }
+            let mut count = 0;
+            for (entity, lifetime) in bullets.iter(&app.world) {
+                count += 1;
+                let age = now - *fired.entry(entity).or_insert(now);
+                // 子弹在寿命结束的那一帧才被移除
+                assert!(
+                    age <= lifetime.0.duration() + FRAME_TIME,
+                    "bullet {:?} alive for {:?}",
+                    entity,
+                    age
+                );
+            }
+            assert!(count <= MAX_BULLETS, "{} bullets alive", count);
+        }
+        assert!(!fired.is_empty(), "no bullets were fired");
+    }
+
+    #[test]
+    fn replay_reproduces_run() {
+        let path =
+            std::env::temp_dir().join(format!("vector-survivor-{}.replay", std::process::id()));
+        // 录制时的可见区域和回放时的默认值不同, 回放必须使用录像中的大小
+        let mut recording = app_with(|app| {
+            app.add_plugins(ReplayPlugin::Record(path.clone()))
+                .insert_resource(Viewport(Vec2::new(800f32, 600f32)));
+        });
+        let report = run(&mut recording);
+        // 游戏结束时录像已经保存到文件
+        let replay = match &recording.world.resource::<ReplayRecorder>().replay {
+            Some(replay) => replay.clone(),
+            None => Replay::load(&path).expect("replay was not saved"),
+        };
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(replay.viewport, Some(Vec2::new(800f32, 600f32)));
+
+        let replay = Replay::decode(&replay.encode()).unwrap();
+        let mut playback = app_with(|app| {
+            app.add_plugins(ReplayPlugin::Playback(replay));
+        });
+        assert_eq!(run(&mut playback), report);
+    }
+}
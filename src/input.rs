@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+/// 这一帧玩家按下的方向键, 由键盘或者回放写入
+#[derive(Resource, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct PlayerInput(pub u8);
+
+impl PlayerInput {
+    pub const UP: u8 = 1;
+    pub const DOWN: u8 = 1 << 1;
+    pub const LEFT: u8 = 1 << 2;
+    pub const RIGHT: u8 = 1 << 3;
+
+    pub fn pressed(&self, key: u8) -> bool {
+        self.0 & key != 0
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        let mut direction = Vec2::ZERO;
+        if self.pressed(Self::UP) {
+            direction.y += 1f32;
+        }
+        if self.pressed(Self::DOWN) {
+            direction.y -= 1f32;
+        }
+        if self.pressed(Self::RIGHT) {
+            direction.x += 1f32;
+        }
+        if self.pressed(Self::LEFT) {
+            direction.x -= 1f32;
+        }
+        direction
+    }
+}
+
+pub fn read_keyboard_input(keys: Res<Input<KeyCode>>, mut input: ResMut<PlayerInput>) {
+    let mut bits = 0;
+    if keys.any_pressed([KeyCode::Up, KeyCode::W]) {
+        bits |= PlayerInput::UP;
+    }
+    if keys.any_pressed([KeyCode::Down, KeyCode::S]) {
+        bits |= PlayerInput::DOWN;
+    }
+    if keys.any_pressed([KeyCode::Right, KeyCode::D]) {
+        bits |= PlayerInput::RIGHT;
+    }
+    if keys.any_pressed([KeyCode::Left, KeyCode::A]) {
+        bits |= PlayerInput::LEFT;
+    }
+    input.0 = bits;
+}
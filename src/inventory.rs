@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::components::{
+    BulletDamage, BulletSpeed, FireRate, GameEntity, LastFire, Player, TargetCount, Weapon,
+    WeaponLevel,
+};
+use crate::events::{PropsUpdateEvent, UpgradeSelectedEvent};
+use crate::stats::{Stat, Stats};
+use crate::upgrades::Offer;
+use crate::weapons::{WeaponDef, WeaponDefs};
+
+/// 已持有的武器和等级
+pub fn owned_weapons<'a>(
+    weapons: impl IntoIterator<Item = (&'a Weapon, &'a WeaponLevel)>,
+) -> HashMap<String, u32> {
+    weapons
+        .into_iter()
+        .map(|(weapon, level)| (weapon.id.clone(), level.0))
+        .collect()
+}
+
+pub fn spawn_weapon(commands: &mut Commands, weapon: &WeaponDef, slot: usize) {
+    let (fire_rate, speed, damage, count) = weapon.stats_at(1);
+    commands.spawn((
+        GameEntity,
+        Weapon {
+            id: weapon.id.clone(),
+            slot,
+        },
+        WeaponLevel(1),
+        LastFire(Duration::ZERO),
+        FireRate(fire_rate),
+        BulletSpeed(speed),
+        BulletDamage(damage),
+        TargetCount(count),
+    ));
+}
+
+/// 选中武器选项时升级已有的武器, 没有则放进新的武器栏, 进化时在原来的武器栏换成进化后的武器
+pub fn apply_weapon_offer(
+    mut commands: Commands,
+    mut ev_upgrade_selected: EventReader<UpgradeSelectedEvent>,
+    mut inventory: Query<(&mut Weapon, &mut WeaponLevel)>,
+    weapons: WeaponDefs,
+) {
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    for UpgradeSelectedEvent(offer) in ev_upgrade_selected.read() {
+        match offer {
+            Offer::Evolve(id) => {
+                let Some(evolution) = weapons.evolution(id) else {
+                    continue;
+                };
+                if let Some((mut weapon, mut level)) = inventory
+                    .iter_mut()
+                    .find(|(owned, _)| owned.id == evolution.weapon)
+                {
+                    info!("{} evolved into {}", weapon.id, evolution.into);
+                    weapon.id = evolution.into.clone();
+                    // 保留等级, 属性在武器改变后重新计算
+                    if let Some(evolved) = weapons.get(&evolution.into) {
+                        level.0 = level.0.min(evolved.max_level);
+                    }
+                }
+            }
+            Offer::Weapon(id) => {
+                let Some(weapon) = weapons.get(id) else {
+                    continue;
+                };
+                let slots = inventory.iter().count();
+                match inventory
+                    .iter_mut()
+                    .find(|(owned, _)| owned.id == weapon.id)
+                {
+                    Some((_, mut level)) => level.0 = (level.0 + 1).min(weapon.max_level),
+                    None if slots < weapons.max_slots => spawn_weapon(&mut commands, weapon, slots),
+                    None => warn!("no free weapon slot for `{}`", weapon.id),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 武器的实际属性为武器等级对应的属性加上玩家属性的加成
+pub fn apply_weapon_stats(
+    players: Query<Ref<Stats>, With<Player>>,
+    mut inventory: Query<
+        (
+            Ref<Weapon>,
+            Ref<WeaponLevel>,
+            &mut FireRate,
+            &mut BulletSpeed,
+            &mut BulletDamage,
+            &mut TargetCount,
+        ),
+        Without<Player>,
+    >,
+    weapons: WeaponDefs,
+    mut ev_props_update: EventWriter<PropsUpdateEvent>,
+) {
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    let Ok(stats) = players.get_single() else {
+        return;
+    };
+    for (weapon, level, mut fire_rate, mut bullet_speed, mut bullet_damage, mut target_count) in
+        &mut inventory
+    {
+        if !stats.is_changed() && !weapon.is_changed() && !level.is_changed() {
+            continue;
+        }
+        let Some(def) = weapons.get(&weapon.id) else {
+            continue;
+        };
+        let (rate, speed, damage, count) = def.stats_at(level.0);
+        fire_rate.0 = rate * stats.get(Stat::FireRate) / stats.get(Stat::Cooldown);
+        bullet_speed.0 = speed * stats.get(Stat::BulletSpeed);
+        bullet_damage.0 = damage * stats.get(Stat::BulletDamage);
+        target_count.0 = (count as f32 + stats.get(Stat::TargetCount))
+            .round()
+            .max(0f32) as usize;
+        ev_props_update.send(PropsUpdateEvent);
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::assets::ItemAssets;
+use crate::components::Player;
+use crate::events::UpgradeSelectedEvent;
+use crate::settings::Language;
+use crate::stats::{Modifier, ModifierSource, Stats};
+use crate::upgrades::{Offer, Rarity, UpgradeEffect};
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Items {
+    // 最多同时持有的被动道具数量
+    pub max_slots: usize,
+    #[serde(rename = "item")]
+    pub items: Vec<Item>,
+}
+
+impl Items {
+    pub fn get(&self, id: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// 可以出现在升级选项中的道具, 已有的道具可以升级, 还有空位时提供新道具
+    pub fn offers<'a>(&'a self, owned: &'a OwnedItems) -> impl Iterator<Item = &'a Item> {
+        let slots_left = owned.0.len() < self.max_slots;
+        self.items
+            .iter()
+            .filter(move |item| match owned.0.get(&item.id) {
+                Some(level) => *level < item.max_level,
+                None => slots_left,
+            })
+    }
+}
+
+/// 被动道具, 每一级都会再次获得 `effects` 中的效果
+#[derive(Debug, Clone, Deserialize)]
+pub struct Item {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    pub description: String,
+    #[serde(default)]
+    pub description_en: Option<String>,
+    #[serde(default)]
+    pub rarity: Rarity,
+    pub max_level: u32,
+    pub effects: Vec<UpgradeEffect>,
+}
+
+impl Item {
+    pub fn localized_name(&self, language: Language) -> &str {
+        language.pick_data(&self.name, self.name_en.as_deref())
+    }
+
+    pub fn localized_description(&self, language: Language) -> &str {
+        language.pick_data(&self.description, self.description_en.as_deref())
+    }
+}
+
+/// 持有的被动道具和等级
+#[derive(Component, Debug, Default)]
+pub struct OwnedItems(pub HashMap<String, u32>);
+
+pub fn apply_item_offer(
+    mut ev_upgrade_selected: EventReader<UpgradeSelectedEvent>,
+    item_assets: Res<ItemAssets>,
+    items: Res<Assets<Items>>,
+    mut players: Query<(&mut OwnedItems, &mut Stats), With<Player>>,
+) {
+    let Some(items) = items.get(&item_assets.items) else {
+        return;
+    };
+    for UpgradeSelectedEvent(offer) in ev_upgrade_selected.read() {
+        let Offer::Item(id) = offer else {
+            continue;
+        };
+        let Some(item) = items.get(id) else {
+            continue;
+        };
+        let (mut owned, mut stats) = players.single_mut();
+        let slots = owned.0.len();
+        match owned.0.get_mut(&item.id) {
+            Some(level) if *level >= item.max_level => continue,
+            Some(level) => *level += 1,
+            None if slots < items.max_slots => {
+                owned.0.insert(item.id.clone(), 1);
+            }
+            None => {
+                warn!("no free item slot for `{}`", item.id);
+                continue;
+            }
+        }
+        for effect in &item.effects {
+            stats.add_modifier(Modifier::new(
+                effect.stat,
+                effect.op,
+                effect.value,
+                ModifierSource::Item(item.id.clone()),
+            ));
+        }
+    }
+}
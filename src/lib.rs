@@ -0,0 +1,38 @@
+pub mod assets;
+pub mod audio;
+pub mod characters;
+pub mod components;
+pub mod enemies;
+pub mod events;
+pub mod fire;
+pub mod gameplay;
+pub mod headless;
+pub mod input;
+pub mod inventory;
+pub mod items;
+pub mod melee;
+pub mod movement;
+pub mod pause;
+pub mod pickups;
+pub mod profile;
+pub mod replay;
+pub mod rng;
+pub mod settings;
+pub mod spatial;
+pub mod states;
+pub mod stats;
+pub mod title;
+pub mod tutorial;
+pub mod ui;
+pub mod upgrades;
+pub mod waves;
+pub mod weapons;
+
+pub use audio::AudioPlugin;
+pub use gameplay::GameplayPlugin;
+pub use headless::HeadlessPlugin;
+pub use pause::PausePlugin;
+pub use settings::SettingsPlugin;
+pub use title::TitlePlugin;
+pub use tutorial::TutorialPlugin;
+pub use ui::UiPlugin;
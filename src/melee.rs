@@ -0,0 +1,288 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::{Disc, Rectangle, ShapeBundle, ShapeConfig, ShapeFill};
+
+use crate::assets::RunClock;
+use crate::components::{
+    Aura, BladeHits, Enemy, GameEntity, Health, HitEnemies, LastFire, OrbitBlade, Player,
+    Shockwave, ShockwaveEmitter, XpReward,
+};
+use crate::events::DamageEvents;
+use crate::movement::damage_enemy;
+use crate::spatial::SpatialIndex;
+use crate::stats::{Stat, Stats};
+use crate::weapons::WeaponDefs;
+
+// 索引里只有敌人的中心点, 判定范围加上敌人的大致半径
+const ENEMY_REACH: f32 = 10.0;
+
+/// 近战武器查找和伤害范围内的敌人
+#[derive(SystemParam)]
+pub struct EnemyTargets<'w, 's> {
+    enemies: Query<
+        'w,
+        's,
+        (&'static XpReward, &'static Transform, &'static mut Health),
+        (With<Enemy>, Without<Player>),
+    >,
+    index: Res<'w, SpatialIndex<Enemy>>,
+    events: DamageEvents<'w>,
+}
+
+impl EnemyTargets<'_, '_> {
+    /// 中心点在范围内的敌人, 范围会加上敌人的大致半径
+    pub fn within(&self, center: Vec2, radius: f32) -> Vec<Entity> {
+        self.index
+            .within(center, radius + ENEMY_REACH)
+            .map(|(enemy, _)| enemy)
+            .collect()
+    }
+
+    /// 敌人已经死亡或不存在时返回 false
+    pub fn damage(&mut self, commands: &mut Commands, enemy: Entity, damage: f32) -> bool {
+        let Ok((xp, transform, mut health)) = self.enemies.get_mut(enemy) else {
+            return false;
+        };
+        if health.0 <= 0f32 {
+            return false;
+        }
+        let position = transform.translation.truncate();
+        damage_enemy(
+            commands,
+            &mut self.events,
+            enemy,
+            &mut health,
+            damage,
+            xp.0,
+            position,
+        );
+        true
+    }
+}
+
+/// 刀刃数量跟随 `Stat::OrbitCount`, 绕着玩家旋转并伤害碰到的敌人
+pub fn update_orbit_blades(
+    mut commands: Commands,
+    players: Query<(&Transform, &Stats), With<Player>>,
+    mut blades: Query<
+        (
+            Entity,
+            &OrbitBlade,
+            &mut Transform,
+            &mut Rectangle,
+            &mut BladeHits,
+        ),
+        (Without<Player>, Without<Enemy>),
+    >,
+    mut targets: EnemyTargets,
+    time: Res<Time>,
+    clock: Res<RunClock>,
+    weapons: WeaponDefs,
+) {
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    let orbit = &weapons.orbit;
+    let Ok((player, stats)) = players.get_single() else {
+        return;
+    };
+    let count = stats.get(Stat::OrbitCount).round().max(0f32) as usize;
+    let damage = stats.get(Stat::OrbitDamage);
+    let size = orbit.size * stats.get(Stat::Area);
+    let center = player.translation.truncate();
+    let placement = |index: usize| {
+        let angle = clock.0.elapsed_secs() * orbit.spin + index as f32 * TAU / count as f32;
+        let position = center + Vec2::from_angle(angle) * orbit.distance;
+        Transform::from_translation(position.extend(0f32))
+            .with_rotation(Quat::from_rotation_z(angle))
+    };
+
+    // 数量变化时重新生成所有刀刃, 保持均匀分布
+    if blades.iter().count() != count {
+        for (entity, ..) in &blades {
+            commands.entity(entity).despawn_recursive();
+        }
+        for index in 0..count {
+            commands.spawn((
+                ShapeBundle::rect(
+                    &ShapeConfig {
+                        color: orbit.color(),
+                        transform: placement(index),
+                        ..ShapeConfig::default_2d()
+                    },
+                    Vec2::new(size * 3f32, size),
+                ),
+                GameEntity,
+                OrbitBlade(index),
+                BladeHits::default(),
+            ));
+        }
+        return;
+    }
+
+    for (_, blade, mut transform, mut rect, mut hits) in &mut blades {
+        *transform = placement(blade.0);
+        rect.size = Vec2::new(size * 3f32, size);
+        hits.0.retain_mut(|(_, cooldown)| {
+            *cooldown -= time.delta_seconds();
+            *cooldown > 0f32
+        });
+        let position = transform.translation.truncate();
+        for enemy in targets.within(position, size * 1.5) {
+            if hits.0.iter().any(|(hit, _)| *hit == enemy) {
+                continue;
+            }
+            if targets.damage(&mut commands, enemy, damage) {
+                hits.0.push((enemy, orbit.hit_interval));
+            }
+        }
+    }
+}
+
+/// 光环跟随玩家, 每隔一段时间伤害范围内的所有敌人
+pub fn update_aura(
+    mut commands: Commands,
+    players: Query<(&Transform, &Stats), With<Player>>,
+    mut auras: Query<
+        (Entity, &mut Aura, &mut Transform, &mut Disc),
+        (Without<Player>, Without<Enemy>),
+    >,
+    mut targets: EnemyTargets,
+    time: Res<Time>,
+    weapons: WeaponDefs,
+) {
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    let Ok((player, stats)) = players.get_single() else {
+        return;
+    };
+    let radius = stats.get(Stat::AuraRadius) * stats.get(Stat::Area);
+    let center = player.translation.truncate();
+    let Ok((entity, mut aura, mut transform, mut disc)) = auras.get_single_mut() else {
+        if radius > 0f32 {
+            commands.spawn((
+                ShapeBundle::circle(
+                    &ShapeConfig {
+                        color: weapons.aura.color(),
+                        transform: Transform::from_translation(center.extend(-1f32)),
+                        ..ShapeConfig::default_2d()
+                    },
+                    radius,
+                ),
+                GameEntity,
+                Aura(Timer::from_seconds(weapons.aura.tick, TimerMode::Repeating)),
+            ));
+        }
+        return;
+    };
+    if radius <= 0f32 {
+        commands.entity(entity).despawn_recursive();
+        return;
+    }
+    transform.translation = center.extend(-1f32);
+    disc.radius = radius;
+
+    if !aura.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    // 伤害按每秒计算
+    let damage = stats.get(Stat::AuraDamage) * aura.0.duration().as_secs_f32();
+    for enemy in targets.within(center, radius) {
+        targets.damage(&mut commands, enemy, damage);
+    }
+}
+
+pub fn fire_shockwave(
+    mut commands: Commands,
+    players: Query<(&Transform, &Stats), With<Player>>,
+    clock: Res<RunClock>,
+    weapons: WeaponDefs,
+    mut emitters: Query<&mut LastFire, With<ShockwaveEmitter>>,
+) {
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    let Ok((player, stats)) = players.get_single() else {
+        return;
+    };
+    // pre minute
+    let rate = stats.get(Stat::ShockwaveRate) / stats.get(Stat::Cooldown);
+    if rate <= 0f32 {
+        return;
+    }
+    let interval = Duration::from_secs_f32(60f32 / rate);
+    let now = clock.0.elapsed();
+    match emitters.get_single_mut() {
+        Ok(mut last_wave) => {
+            if now.saturating_sub(last_wave.0) < interval {
+                return;
+            }
+            last_wave.0 = now;
+        }
+        // 第一次发出冲击波时生成武器实体, 随本局的其他实体一起清理
+        Err(_) => {
+            commands.spawn((GameEntity, ShockwaveEmitter, LastFire(now)));
+        }
+    }
+    commands.spawn((
+        ShapeBundle::circle(
+            &ShapeConfig {
+                color: weapons.shockwave.color(),
+                transform: Transform::from_translation(player.translation.truncate().extend(-1f32)),
+                hollow: true,
+                thickness: weapons.shockwave.thickness,
+                ..ShapeConfig::default_2d()
+            },
+            0f32,
+        ),
+        GameEntity,
+        Shockwave {
+            timer: Timer::from_seconds(weapons.shockwave.duration, TimerMode::Once),
+            radius: stats.get(Stat::ShockwaveRadius) * stats.get(Stat::Area),
+            damage: stats.get(Stat::ShockwaveDamage),
+        },
+        HitEnemies::default(),
+    ));
+}
+
+/// 冲击波从发出的位置向外扩散, 波前经过的敌人各受到一次伤害
+pub fn expand_shockwaves(
+    mut commands: Commands,
+    mut waves: Query<
+        (
+            Entity,
+            &mut Shockwave,
+            &mut HitEnemies,
+            &Transform,
+            &mut Disc,
+            &mut ShapeFill,
+        ),
+        Without<Enemy>,
+    >,
+    mut targets: EnemyTargets,
+    time: Res<Time>,
+) {
+    for (entity, mut wave, mut hit, transform, mut disc, mut fill) in &mut waves {
+        let progress = wave.timer.tick(time.delta()).percent();
+        let radius = wave.radius * progress;
+        disc.radius = radius;
+        fill.color.set_a(1f32 - progress);
+
+        let center = transform.translation.truncate();
+        for enemy in targets.within(center, radius) {
+            if hit.0.contains(&enemy) {
+                continue;
+            }
+            if targets.damage(&mut commands, enemy, wave.damage) {
+                hit.0.push(enemy);
+            }
+        }
+        if wave.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
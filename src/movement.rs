@@ -1,15 +1,26 @@
+use bevy::ecs::system::SystemParam;
 use bevy::input::mouse::MouseMotion;
 use bevy::prelude::{
-    Camera, Commands, DespawnRecursiveExt, Entity, EventReader, EventWriter, Input, KeyCode,
-    MouseButton, NextState, Quat, Query, Res, ResMut, Time, TouchInput, Transform, Vec2, Vec3,
-    Vec3Swizzles, With, Without,
+    Camera, Commands, DespawnRecursiveExt, Entity, EventReader, EventWriter, Has, Input,
+    MouseButton, NextState, Quat, Query, Res, ResMut, Time, Timer, TimerMode, TouchInput,
+    Transform, Vec2, Vec3, Vec3Swizzles, Visibility, With, Without,
 };
+use bevy::utils::HashSet;
 use bevy_xpbd_2d::prelude::{AngularVelocity, Collision, LinearVelocity};
 
-use crate::components::{AttackTarget, Bullet, MoveSpeed};
-use crate::events::KillEvent;
+use crate::assets::RunClock;
+use crate::components::{
+    AttackTarget, Bullet, BulletLifetime, BulletRange, ContactDamage, Damage, Health, HitEnemies,
+    HitFeedback, Homing, Invulnerable, MovePhase, MoveSpeed, Pierce, Retarget, Ricochet,
+    SourceWeapon, Split, XpReward,
+};
+use crate::components::{Enemy, Player};
+use crate::enemies::Behaviour;
+use crate::events::{BulletSplitEvent, DamageEvent, DamageEvents, KillEvent, PropsUpdateEvent};
+use crate::input::PlayerInput;
+use crate::spatial::SpatialIndex;
 use crate::states::AppState;
-use crate::{Enemy, Player};
+use crate::stats::{Stat, Stats};
 
 pub fn move_player_with_mouse(
     mouse: Res<Input<MouseButton>>,
@@ -59,23 +70,11 @@ pub fn move_player_with_touch(
 }
 
 pub fn move_player(
-    keys: Res<Input<KeyCode>>,
+    input: Res<PlayerInput>,
     time: Res<Time>,
     mut players: Query<(&mut Transform, &MoveSpeed), With<Player>>,
 ) {
-    let mut direction = Vec2::ZERO;
-    if keys.any_pressed([KeyCode::Up, KeyCode::W]) {
-        direction.y += 1f32;
-    }
-    if keys.any_pressed([KeyCode::Down, KeyCode::S]) {
-        direction.y -= 1f32;
-    }
-    if keys.any_pressed([KeyCode::Right, KeyCode::D]) {
-        direction.x += 1f32;
-    }
-    if keys.any_pressed([KeyCode::Left, KeyCode::A]) {
-        direction.x -= 1f32;
-    }
+    let direction = input.direction();
     if direction == Vec2::ZERO {
         return;
     }
@@ -90,10 +89,13 @@ pub fn move_player(
 pub fn enemy_approaches_player(
     players: Query<&Transform, With<Player>>,
     time: Res<Time>,
+    clock: Res<RunClock>,
     mut enemies: Query<
         (
             &mut Transform,
             &MoveSpeed,
+            &MovePhase,
+            &Behaviour,
             &mut LinearVelocity,
             &mut AngularVelocity,
         ),
@@ -101,15 +103,31 @@ pub fn enemy_approaches_player(
     >,
 ) {
     let player = players.get_single().unwrap();
+    let elapsed = clock.0.elapsed_secs();
     enemies
         .par_iter_mut()
-        .for_each(|(mut enemy, speed, mut l, mut a)| {
+        .for_each(|(mut enemy, speed, phase, behaviour, mut l, mut a)| {
             l.0 = Vec2::ZERO;
             a.0 = 0.0;
             let z = enemy.translation.z;
+            let phase = elapsed + phase.0;
             enemy.rotation = rotate_to(enemy.translation.xy(), player.translation.xy());
+            let speed = match behaviour {
+                Behaviour::Chase => speed.0,
+                Behaviour::Zigzag => {
+                    enemy.rotate_z((phase * 5f32).sin() * 0.8);
+                    speed.0
+                }
+                Behaviour::Dash => {
+                    if phase % 2f32 < 0.5 {
+                        speed.0 * 3f32
+                    } else {
+                        speed.0 * 0.3
+                    }
+                }
+            };
             let local_y = enemy.local_y();
-            enemy.translation += local_y * speed.0 * time.delta_seconds();
+            enemy.translation += local_y * speed * time.delta_seconds();
             enemy.translation.z = z;
         });
     /*for (mut enemy, speed, mut l, mut a) in &mut enemies {
@@ -124,73 +142,327 @@ pub fn enemy_approaches_player(
 }
 
 pub fn move_bullet(
-    mut bullets: Query<(&mut Transform, &MoveSpeed, &mut AttackTarget), With<Bullet>>,
+    mut bullets: Query<
+        (
+            &mut Transform,
+            &MoveSpeed,
+            &mut AttackTarget,
+            &mut BulletRange,
+            &HitEnemies,
+            Option<&Homing>,
+            Has<Retarget>,
+        ),
+        With<Bullet>,
+    >,
     enemies: Query<&Transform, (With<Enemy>, Without<Bullet>)>,
+    index: Res<SpatialIndex<Enemy>>,
     time: Res<Time>,
 ) {
-    bullets
-        .par_iter_mut()
-        .for_each(|(mut transform, speed, target)| {
-            if let Ok(enemy) = enemies.get(target.0) {
-                transform.rotation = rotate_to(transform.translation.xy(), enemy.translation.xy());
-                //*direction = move_to(&transform.translation, &enemy.translation)
+    bullets.par_iter_mut().for_each(
+        |(mut transform, speed, mut target, mut range, hit, homing, retarget)| {
+            let position = transform.translation.xy();
+            // 没有追踪能力的子弹沿发射方向直线飞行
+            if let Some(Homing(turn_rate)) = homing {
+                let aim = match enemies.get(target.0) {
+                    Ok(enemy) => Some(enemy.translation.xy()),
+                    // 目标已经死亡, 换成离子弹最近且没有命中过的敌人
+                    Err(_) if retarget => index
+                        .nearest(position, hit.0.len() + 1)
+                        .into_iter()
+                        .find(|(entity, _)| !hit.0.contains(entity))
+                        .map(|(entity, enemy)| {
+                            target.0 = entity;
+                            enemy
+                        }),
+                    // 否则沿着最后的方向继续飞
+                    Err(_) => None,
+                };
+                if let Some(aim) = aim {
+                    // 每秒最多转 turn_rate 弧度
+                    let heading = transform.local_y().xy();
+                    let max_turn = turn_rate * time.delta_seconds();
+                    let turn = heading
+                        .angle_between(aim - position)
+                        .clamp(-max_turn, max_turn);
+                    if turn.is_finite() {
+                        transform.rotate_z(turn);
+                    }
+                }
             }
 
             let z = transform.translation.z;
-            //transform.translation += (*direction * speed.0).extend(0f32);
+            let step = speed.0 * time.delta_seconds();
             let local_y = transform.local_y();
-            transform.translation += local_y * speed.0 * time.delta_seconds();
+            transform.translation += local_y * step;
             transform.translation.z = z;
-        });
-    /*for (mut transform, speed, target) in &mut bullets {
-        if let Ok(enemy) = enemies.get(target.0) {
-            transform.rotation = rotate_to(transform.translation.xy(), enemy.translation.xy());
-            // *direction = move_to(&transform.translation, &enemy.translation)
+            range.0 -= step;
+        },
+    );
+}
+
+pub fn expire_bullets(
+    mut commands: Commands,
+    mut bullets: Query<(Entity, &mut BulletLifetime, &BulletRange), With<Bullet>>,
+    time: Res<Time>,
+) {
+    for (entity, mut lifetime, range) in &mut bullets {
+        if lifetime.0.tick(time.delta()).finished() || range.0 <= 0f32 {
+            commands.entity(entity).despawn_recursive();
         }
+    }
+}
 
-        let z = transform.translation.z;
-        //transform.translation += (*direction * speed.0).extend(0f32);
-        let local_y = transform.local_y();
-        transform.translation += local_y * speed.0 * time.delta_seconds();
-        transform.translation.z = z;
-    }*/
+const INVULNERABLE_SECONDS: f32 = 0.8;
+const BLINK_SECONDS: f32 = 0.1;
+const HIT_FEEDBACK_SECONDS: f32 = 0.15;
+
+/// 敌人碰到玩家时扣除生命, 生命归零时结束本局
+#[derive(SystemParam)]
+pub struct PlayerHits<'w, 's> {
+    players: Query<
+        'w,
+        's,
+        (&'static mut Health, &'static Stats, Has<Invulnerable>),
+        (With<Player>, (Without<Bullet>, Without<Enemy>)),
+    >,
+    next_state: ResMut<'w, NextState<AppState>>,
+    ev_props_update: EventWriter<'w, PropsUpdateEvent>,
+}
+
+impl PlayerHits<'_, '_> {
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.players.contains(entity)
+    }
+
+    /// 无敌时不受伤害, 返回是否受到了伤害
+    pub fn hurt(&mut self, commands: &mut Commands, player: Entity, damage: f32) -> bool {
+        let Ok((mut health, stats, invulnerable)) = self.players.get_mut(player) else {
+            return false;
+        };
+        if invulnerable {
+            return false;
+        }
+        // 护甲减免固定数值, 但至少受到 1 点伤害
+        let damage = (damage - stats.get(Stat::Armor)).max(1f32);
+        health.0 = (health.0 - damage).max(0f32);
+        self.ev_props_update.send(PropsUpdateEvent);
+        if health.0 <= 0f32 {
+            self.next_state.set(AppState::GameOver);
+        } else {
+            commands
+                .entity(player)
+                .insert(Invulnerable(Timer::from_seconds(
+                    INVULNERABLE_SECONDS,
+                    TimerMode::Once,
+                )));
+        }
+        true
+    }
+}
+
+/// 子弹命中敌人时发送的事件
+#[derive(SystemParam)]
+pub struct BulletHitEvents<'w> {
+    damage: DamageEvents<'w>,
+    split: EventWriter<'w, BulletSplitEvent>,
 }
 
 pub fn bullet_collision(
     mut collision_event_reader: EventReader<Collision>,
-    bullets: Query<Entity, With<Bullet>>,
-    enemies: Query<Entity, (With<Enemy>, Without<Bullet>)>,
-    players: Query<Entity, (With<Player>, (Without<Bullet>, Without<Enemy>))>,
+    mut bullets: Query<
+        (
+            &Damage,
+            &mut Transform,
+            &MoveSpeed,
+            &SourceWeapon,
+            &mut HitEnemies,
+            &mut AttackTarget,
+            Option<&mut Pierce>,
+            Option<&mut Ricochet>,
+            Option<&Split>,
+        ),
+        With<Bullet>,
+    >,
+    mut enemies: Query<
+        (Entity, &ContactDamage, &XpReward, &Transform, &mut Health),
+        (With<Enemy>, Without<Bullet>, Without<Player>),
+    >,
+    mut players: PlayerHits,
+    index: Res<SpatialIndex<Enemy>>,
     mut command: Commands,
-    mut next_state: ResMut<NextState<AppState>>,
-    mut ev_kill: EventWriter<KillEvent>,
+    mut events: BulletHitEvents,
 ) {
+    // 同一帧内的多次碰撞只结算一次伤害
+    let mut hurt = false;
+    let mut consumed = HashSet::new();
     for Collision(contacts) in collision_event_reader.read() {
+        let bullet = if bullets.contains(contacts.entity1) {
+            Ok(contacts.entity1)
+        } else if bullets.contains(contacts.entity2) {
+            Ok(contacts.entity2)
+        } else {
+            Err(())
+        };
         match (
-            bullets
-                .get(contacts.entity1)
-                .or_else(|_| bullets.get(contacts.entity2)),
+            bullet,
             enemies
                 .get(contacts.entity1)
-                .or_else(|_| enemies.get(contacts.entity2)),
-            players
-                .get(contacts.entity1)
-                .or_else(|_| players.get(contacts.entity2)),
+                .or_else(|_| enemies.get(contacts.entity2))
+                .map(|(enemy, damage, reward, transform, _)| {
+                    (enemy, damage.0, reward.0, transform.translation.xy())
+                }),
+            if players.contains(contacts.entity1) {
+                Ok(contacts.entity1)
+            } else if players.contains(contacts.entity2) {
+                Ok(contacts.entity2)
+            } else {
+                Err(())
+            },
         ) {
-            (Ok(bullet), Ok(enemy), Err(_)) => {
+            (Ok(bullet), Ok((enemy, _, xp, position)), Err(_)) => {
+                if consumed.contains(&bullet) {
+                    continue;
+                }
+                let (
+                    damage,
+                    mut transform,
+                    speed,
+                    weapon,
+                    mut hit,
+                    mut target,
+                    pierce,
+                    ricochet,
+                    split,
+                ) = bullets.get_mut(bullet).unwrap();
+                let (.., mut health) = enemies.get_mut(enemy).unwrap();
+                if health.0 <= 0f32 || hit.0.contains(&enemy) {
+                    continue;
+                }
+                hit.0.push(enemy);
+                damage_enemy(
+                    &mut command,
+                    &mut events.damage,
+                    enemy,
+                    &mut health,
+                    damage.0,
+                    xp,
+                    position,
+                );
+
+                // 优先弹射到附近的敌人, 其次穿透, 都没有了子弹才消失
+                let from = transform.translation.xy();
+                if let Some(mut ricochet) = ricochet.filter(|ricochet| ricochet.remaining > 0) {
+                    let next = index
+                        .within(from, ricochet.range)
+                        .filter(|(entity, _)| {
+                            !hit.0.contains(entity)
+                                && enemies
+                                    .get(*entity)
+                                    .is_ok_and(|(.., health)| health.0 > 0f32)
+                        })
+                        .min_by(|(_, l), (_, r)| {
+                            l.distance_squared(from)
+                                .total_cmp(&r.distance_squared(from))
+                        });
+                    if let Some((next, aim)) = next {
+                        ricochet.remaining -= 1;
+                        target.0 = next;
+                        transform.rotation = rotate_to(from, aim);
+                        continue;
+                    }
+                }
+                if let Some(mut pierce) = pierce.filter(|pierce| pierce.0 > 0) {
+                    pierce.0 -= 1;
+                    // 穿过去之后不再追这个敌人
+                    target.0 = Entity::PLACEHOLDER;
+                    continue;
+                }
+                consumed.insert(bullet);
                 command.entity(bullet).despawn_recursive();
-                command.entity(enemy).despawn_recursive();
-                ev_kill.send(KillEvent);
+                if let Some(split) = split {
+                    events.split.send(BulletSplitEvent {
+                        weapon: weapon.0.clone(),
+                        position: from,
+                        direction: transform.local_y().xy(),
+                        count: split.count,
+                        damage: damage.0 * split.damage,
+                        speed: speed.0,
+                        hit: hit.0.clone(),
+                    });
+                }
             }
-            (Err(_), Ok(_), Ok(_)) => {
-                next_state.set(AppState::GameOver);
-                //next_state.set(AppState::Restart);
+            (Err(_), Ok((_, damage, ..)), Ok(player)) => {
+                if !hurt {
+                    hurt = players.hurt(&mut command, player, damage);
+                }
             }
             _ => continue,
         }
     }
 }
 
+/// 扣除敌人的生命, 死亡时移除敌人并发出击杀事件
+pub fn damage_enemy(
+    commands: &mut Commands,
+    events: &mut DamageEvents,
+    enemy: Entity,
+    health: &mut Health,
+    damage: f32,
+    xp: usize,
+    position: Vec2,
+) {
+    events.damage.send(DamageEvent {
+        position,
+        damage: damage.min(health.0),
+    });
+    health.0 -= damage;
+    if health.0 <= 0f32 {
+        commands.entity(enemy).despawn_recursive();
+        events.kill.send(KillEvent { xp, position });
+    } else {
+        commands
+            .entity(enemy)
+            .insert(HitFeedback(Timer::from_seconds(
+                HIT_FEEDBACK_SECONDS,
+                TimerMode::Once,
+            )));
+    }
+}
+
+pub fn tick_hit_feedback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut enemies: Query<(Entity, &mut HitFeedback, &mut Transform), With<Enemy>>,
+) {
+    for (entity, mut hit, mut transform) in &mut enemies {
+        hit.0.tick(time.delta());
+        if hit.0.finished() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<HitFeedback>();
+        } else {
+            transform.scale = Vec3::splat(1f32 + 0.4 * hit.0.percent_left());
+        }
+    }
+}
+
+pub fn tick_invulnerable(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut players: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut players {
+        invulnerable.0.tick(time.delta());
+        if invulnerable.0.finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else if (invulnerable.0.elapsed_secs() / BLINK_SECONDS) as u32 % 2 == 0 {
+            *visibility = Visibility::Hidden;
+        } else {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
 fn rotate_to(source: Vec2, target: Vec2) -> Quat {
     Quat::from_rotation_arc(Vec3::Y, (target - source).normalize().extend(0f32))
 }
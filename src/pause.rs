@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use bevy_xpbd_2d::prelude::PhysicsSet;
+
+use crate::gameplay::GameSet;
+use crate::states::AppState;
+
+/// 暂停游戏: Esc/P 切换, 窗口失去焦点时自动暂停.
+/// 暂停期间虚拟时间和物理模拟都会停下, 本局的游戏时长也不会前进.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_systems(OnEnter(AppState::Paused), freeze_time)
+            .add_systems(OnExit(AppState::Paused), resume_time)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause
+                        .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Paused))),
+                    pause_on_focus_lost.run_if(in_state(AppState::InGame)),
+                )
+                    .in_set(GameSet::Input),
+            )
+            .configure_sets(
+                PostUpdate,
+                PhysicsSet::StepSimulation.run_if(not(in_state(AppState::Paused))),
+            );
+    }
+}
+
+/// 暂停菜单当前显示的页面
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PauseMenu {
+    #[default]
+    Main,
+    Settings,
+}
+
+pub fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::P]) {
+        return;
+    }
+    match state.get() {
+        AppState::InGame => next_state.set(AppState::Paused),
+        AppState::Paused => next_state.set(AppState::InGame),
+        _ => {}
+    }
+}
+
+pub fn pause_on_focus_lost(
+    mut ev_focused: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if ev_focused.read().any(|focused| !focused.focused) {
+        next_state.set(AppState::Paused);
+    }
+}
+
+pub fn freeze_time(mut time: ResMut<Time<Virtual>>, mut menu: ResMut<PauseMenu>) {
+    time.pause();
+    *menu = PauseMenu::Main;
+}
+
+pub fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
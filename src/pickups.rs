@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::{ShapeBundle, ShapeConfig};
+use rand::Rng;
+
+use crate::components::{GameEntity, Magnetized, PickupRadius, Player, XpGem};
+use crate::events::{KillEvent, XpIncEvent};
+use crate::rng::RngStreams;
+use crate::spatial::SpatialIndex;
+
+const GEM_SPEED: f32 = 300.0;
+const COLLECT_DISTANCE: f32 = 8.0;
+// 经验宝石落在击杀位置附近, 避免同一位置的宝石叠在一起
+const DROP_SCATTER: f32 = 6.0;
+
+pub fn drop_xp_gems(
+    mut commands: Commands,
+    mut ev_kill: EventReader<KillEvent>,
+    mut rng: ResMut<RngStreams>,
+) {
+    let rng = &mut rng.loot;
+    for kill in ev_kill.read() {
+        let (color, size) = match kill.xp {
+            0..=2 => (Color::CYAN, 4f32),
+            3..=7 => (Color::LIME_GREEN, 5f32),
+            _ => (Color::FUCHSIA, 7f32),
+        };
+        let scatter = Vec2::new(
+            rng.gen_range(-DROP_SCATTER..=DROP_SCATTER),
+            rng.gen_range(-DROP_SCATTER..=DROP_SCATTER),
+        );
+        commands.spawn((
+            ShapeBundle::rect(
+                &ShapeConfig {
+                    color,
+                    transform: Transform::from_translation((kill.position + scatter).extend(-1f32))
+                        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                    ..ShapeConfig::default_2d()
+                },
+                Vec2::splat(size),
+            ),
+            GameEntity,
+            XpGem(kill.xp),
+        ));
+    }
+}
+
+pub fn attract_xp_gems(
+    mut commands: Commands,
+    players: Query<(&Transform, &PickupRadius), With<Player>>,
+    mut gems: Query<(Entity, &mut Transform, &XpGem), (With<Magnetized>, Without<Player>)>,
+    index: Res<SpatialIndex<XpGem>>,
+    time: Res<Time>,
+    mut ev_xp_inc: EventWriter<XpIncEvent>,
+) {
+    let Ok((player, radius)) = players.get_single() else {
+        return;
+    };
+    let player = player.translation.truncate();
+    for (entity, mut transform, gem) in &mut gems {
+        let position = transform.translation.truncate();
+        let distance = position.distance(player);
+        if distance <= COLLECT_DISTANCE {
+            commands.entity(entity).despawn_recursive();
+            ev_xp_inc.send(XpIncEvent(gem.0));
+        } else {
+            let step = (GEM_SPEED * time.delta_seconds()).min(distance);
+            let z = transform.translation.z;
+            transform.translation = (position + (player - position) / distance * step).extend(z);
+        }
+    }
+    // 进入拾取范围后会一直飞向玩家
+    for (entity, _) in index.within(player, radius.0) {
+        if !gems.contains(entity) {
+            commands.entity(entity).insert(Magnetized);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::io;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const PROFILE_FILE: &str = "profile.toml";
+
+/// 平台配置目录下本游戏的文件, 网页版等没有配置目录的平台上为空
+pub fn config_path(file: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("vector_survivor").join(file))
+}
+
+/// 跨局保存的玩家记录: 是否看过教程和历史统计
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub tutorial_seen: bool,
+    pub runs: u32,
+    pub total_kills: u64,
+    // 最长存活时间, 秒
+    pub best_time: f32,
+    pub best_level: usize,
+}
+
+impl Profile {
+    /// 读取失败时使用新的记录, 不影响游戏
+    pub fn load() -> Profile {
+        let Some(path) = config_path(PROFILE_FILE) else {
+            return Profile::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(s) => toml::from_str(&s).unwrap_or_else(|err| {
+                warn!("ignoring invalid profile {}: {}", path.display(), err);
+                Profile::default()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Profile::default(),
+            Err(err) => {
+                warn!("failed to read profile {}: {}", path.display(), err);
+                Profile::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_path(PROFILE_FILE) else {
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            .and_then(|s| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&path, s)
+            });
+        if let Err(err) = result {
+            error!("failed to save profile to {}: {}", path.display(), err);
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+
+use crate::assets::Viewport;
+use crate::characters::SelectedCharacter;
+use crate::events::{
+    BanishUpgradeEvent, RerollUpgradesEvent, SkipUpgradeEvent, UpgradeActionEvents, UpgradeActions,
+    UpgradeSelectedEvent,
+};
+use crate::gameplay::{setup_game, GameSet};
+use crate::input::{read_keyboard_input, PlayerInput};
+use crate::movement::move_player;
+use crate::rng::RunSeed;
+use crate::states::AppState;
+use crate::upgrades::Offer;
+
+const HEADER: &str = "vector-survivor replay 1";
+
+/// 一局游戏的种子和每一帧的输入
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    // 录制时选择的角色, 旧的录像中没有
+    pub character: Option<String>,
+    // 录制开始时的可见区域大小, 决定敌人生成的位置, 旧的录像中没有
+    pub viewport: Option<Vec2>,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayFrame {
+    pub delta: Duration,
+    pub input: PlayerInput,
+    pub actions: Vec<ReplayAction>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayAction {
+    Select(Offer),
+    Skip,
+    Reroll,
+    Banish(Offer),
+    // 录制过程中窗口大小改变
+    Resize(Vec2),
+}
+
+impl Replay {
+    /// 每帧一行: `帧间隔纳秒 按键 [操作...]`
+    pub fn encode(&self) -> String {
+        let mut s = format!("{}\nseed {}\n", HEADER, self.seed);
+        if let Some(character) = &self.character {
+            let _ = writeln!(s, "character {}", character);
+        }
+        if let Some(viewport) = self.viewport {
+            let _ = writeln!(s, "viewport {} {}", viewport.x, viewport.y);
+        }
+        for frame in &self.frames {
+            let _ = write!(s, "{} {:x}", frame.delta.as_nanos(), frame.input.0);
+            for action in &frame.actions {
+                let _ = match action {
+                    ReplayAction::Select(offer) => write!(s, " s:{}", offer),
+                    ReplayAction::Skip => write!(s, " k"),
+                    ReplayAction::Reroll => write!(s, " r"),
+                    ReplayAction::Banish(offer) => write!(s, " b:{}", offer),
+                    ReplayAction::Resize(size) => write!(s, " v:{}x{}", size.x, size.y),
+                };
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    pub fn decode(s: &str) -> Result<Replay, String> {
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a replay file".to_owned());
+        }
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or("missing seed")?;
+        let mut character = None;
+        let mut lines = lines.peekable();
+        if let Some(id) = lines
+            .peek()
+            .and_then(|line| line.strip_prefix("character "))
+        {
+            character = Some(id.to_owned());
+            lines.next();
+        }
+        let mut viewport = None;
+        if let Some(size) = lines.peek().and_then(|line| line.strip_prefix("viewport ")) {
+            viewport = Some(parse_size(size, ' ').ok_or("invalid viewport")?);
+            lines.next();
+        }
+
+        let mut frames = Vec::new();
+        for (index, line) in lines.enumerate() {
+            let invalid = || format!("invalid frame {}: `{}`", index, line);
+            let mut tokens = line.split(' ');
+            let delta = tokens
+                .next()
+                .and_then(|delta| delta.parse().ok())
+                .map(Duration::from_nanos)
+                .ok_or_else(invalid)?;
+            let input = tokens
+                .next()
+                .and_then(|input| u8::from_str_radix(input, 16).ok())
+                .map(PlayerInput)
+                .ok_or_else(invalid)?;
+            let actions = tokens
+                .map(|token| match token.split_once(':') {
+                    Some(("s", offer)) => Ok(ReplayAction::Select(Offer::parse(offer))),
+                    Some(("b", offer)) => Ok(ReplayAction::Banish(Offer::parse(offer))),
+                    Some(("v", size)) => parse_size(size, 'x')
+                        .map(ReplayAction::Resize)
+                        .ok_or_else(invalid),
+                    None if token == "k" => Ok(ReplayAction::Skip),
+                    None if token == "r" => Ok(ReplayAction::Reroll),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<_, _>>()?;
+            frames.push(ReplayFrame {
+                delta,
+                input,
+                actions,
+            });
+        }
+        Ok(Replay {
+            seed,
+            character,
+            viewport,
+            frames,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Replay> {
+        Replay::decode(&std::fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.encode())
+    }
+}
+
+fn parse_size(s: &str, separator: char) -> Option<Vec2> {
+    let (x, y) = s.split_once(separator)?;
+    Some(Vec2::new(x.parse().ok()?, y.parse().ok()?))
+}
+
+/// 录制每一局到文件, 或者回放一个录像
+pub enum ReplayPlugin {
+    Record(PathBuf),
+    Playback(Replay),
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let in_run = in_state(AppState::InGame).or_else(in_state(AppState::SelectUpgrade));
+        match self {
+            ReplayPlugin::Record(path) => {
+                app.insert_resource(ReplayRecorder {
+                    path: path.clone(),
+                    replay: None,
+                    viewport: Vec2::ZERO,
+                })
+                .add_systems(OnEnter(AppState::Start), start_recording.after(setup_game))
+                .add_systems(
+                    Update,
+                    record_frame.after(GameSet::Progression).run_if(in_run),
+                )
+                .add_systems(OnEnter(AppState::GameOver), save_recording);
+            }
+            ReplayPlugin::Playback(replay) => {
+                app.insert_resource(RunSeed::fixed(replay.seed))
+                    .insert_resource(SelectedCharacter(replay.character.clone()))
+                    .insert_resource(ReplayPlayback {
+                        replay: replay.clone(),
+                        frame: 0,
+                        playing: false,
+                        viewport: replay.viewport.unwrap_or(Viewport::default().0),
+                    })
+                    .add_systems(OnEnter(AppState::Start), start_playback)
+                    .add_systems(First, feed_frame_time.before(TimeSystem))
+                    .add_systems(
+                        Update,
+                        play_back_frame
+                            .in_set(GameSet::Input)
+                            .after(read_keyboard_input)
+                            .before(move_player)
+                            .run_if(in_run),
+                    );
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    pub path: PathBuf,
+    pub replay: Option<Replay>,
+    // 上一帧的可见区域大小, 改变时记录下来
+    pub viewport: Vec2,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub frame: usize,
+    pub playing: bool,
+    // 回放时敌人按录制时的可见区域生成, 不跟随当前窗口
+    pub viewport: Vec2,
+}
+
+fn start_recording(
+    seed: Res<RunSeed>,
+    selected: Res<SelectedCharacter>,
+    viewport: Res<Viewport>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    recorder.viewport = viewport.0;
+    recorder.replay = Some(Replay {
+        seed: seed.current,
+        character: selected.0.clone(),
+        viewport: Some(viewport.0),
+        frames: Vec::new(),
+    });
+}
+
+fn record_frame(
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    viewport: Res<Viewport>,
+    mut events: UpgradeActionEvents,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let recorder = &mut *recorder;
+    let Some(replay) = &mut recorder.replay else {
+        return;
+    };
+    let mut actions = Vec::new();
+    if viewport.0 != recorder.viewport {
+        recorder.viewport = viewport.0;
+        actions.push(ReplayAction::Resize(viewport.0));
+    }
+    actions.extend(
+        events
+            .reroll
+            .read()
+            .map(|_| ReplayAction::Reroll)
+            .chain(
+                events
+                    .banish
+                    .read()
+                    .map(|BanishUpgradeEvent(offer)| ReplayAction::Banish(offer.clone())),
+            )
+            .chain(
+                events
+                    .selected
+                    .read()
+                    .map(|UpgradeSelectedEvent(offer)| ReplayAction::Select(offer.clone())),
+            )
+            .chain(events.skip.read().map(|_| ReplayAction::Skip)),
+    );
+    replay.frames.push(ReplayFrame {
+        delta: time.delta(),
+        input: *input,
+        actions,
+    });
+}
+
+fn save_recording(mut recorder: ResMut<ReplayRecorder>) {
+    let Some(replay) = recorder.replay.take() else {
+        return;
+    };
+    match replay.save(&recorder.path) {
+        Ok(()) => info!(
+            "replay saved to {} ({} frames)",
+            recorder.path.display(),
+            replay.frames.len()
+        ),
+        Err(err) => error!(
+            "failed to save replay to {}: {}",
+            recorder.path.display(),
+            err
+        ),
+    }
+}
+
+fn start_playback(mut playback: ResMut<ReplayPlayback>) {
+    // 只回放第一局
+    playback.playing = playback.frame == 0;
+}
+
+// 按录制时的帧间隔推进时间, 保证每一帧的模拟结果一致
+fn feed_frame_time(playback: Res<ReplayPlayback>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    if !playback.playing {
+        return;
+    }
+    if let Some(frame) = playback.replay.frames.get(playback.frame) {
+        *strategy = TimeUpdateStrategy::ManualDuration(frame.delta);
+    }
+}
+
+fn play_back_frame(
+    mut playback: ResMut<ReplayPlayback>,
+    mut input: ResMut<PlayerInput>,
+    mut viewport: ResMut<Viewport>,
+    mut actions: UpgradeActions,
+) {
+    let playback = &mut *playback;
+    if !playback.playing {
+        return;
+    }
+    let Some(frame) = playback.replay.frames.get(playback.frame) else {
+        info!("replay finished after {} frames", playback.frame);
+        playback.playing = false;
+        return;
+    };
+    *input = frame.input;
+    for action in &frame.actions {
+        match action {
+            ReplayAction::Select(offer) => {
+                actions.selected.send(UpgradeSelectedEvent(offer.clone()))
+            }
+            ReplayAction::Skip => actions.skip.send(SkipUpgradeEvent),
+            ReplayAction::Reroll => actions.reroll.send(RerollUpgradesEvent),
+            ReplayAction::Banish(offer) => actions.banish.send(BanishUpgradeEvent(offer.clone())),
+            ReplayAction::Resize(size) => playback.viewport = *size,
+        }
+    }
+    // 窗口同步在这之前运行, 每帧重新固定
+    if viewport.0 != playback.viewport {
+        viewport.0 = playback.viewport;
+    }
+    playback.frame += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Replay {
+        Replay {
+            seed: 42,
+            character: Some("mage".to_owned()),
+            viewport: Some(Vec2::new(1280f32, 720f32)),
+            frames: vec![
+                ReplayFrame {
+                    delta: Duration::from_nanos(16_666_667),
+                    input: PlayerInput(0b1010),
+                    actions: Vec::new(),
+                },
+                ReplayFrame {
+                    delta: Duration::from_millis(16),
+                    input: PlayerInput(0),
+                    actions: vec![
+                        ReplayAction::Reroll,
+                        ReplayAction::Banish(Offer::Item("clover".to_owned())),
+                        ReplayAction::Select(Offer::Upgrade("move_speed".to_owned())),
+                        ReplayAction::Resize(Vec2::new(1600.5, 900f32)),
+                    ],
+                },
+                ReplayFrame {
+                    delta: Duration::ZERO,
+                    input: PlayerInput(0xff),
+                    actions: vec![
+                        ReplayAction::Select(Offer::Weapon("laser".to_owned())),
+                        ReplayAction::Select(Offer::Evolve("holy_laser".to_owned())),
+                        ReplayAction::Skip,
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let replay = sample();
+        assert_eq!(Replay::decode(&replay.encode()), Ok(replay));
+
+        let replay = Replay {
+            character: None,
+            viewport: None,
+            ..sample()
+        };
+        assert_eq!(Replay::decode(&replay.encode()), Ok(replay));
+    }
+
+    #[test]
+    fn decode_rejects_invalid_files() {
+        assert!(Replay::decode("").is_err());
+        assert!(Replay::decode("vector-survivor replay 0\nseed 1\n").is_err());
+        assert!(Replay::decode(&format!("{}\nseed x\n", HEADER)).is_err());
+        assert!(Replay::decode(&format!("{}\nseed 1\n16 zz\n", HEADER)).is_err());
+        assert!(Replay::decode(&format!("{}\nseed 1\n16 0 q\n", HEADER)).is_err());
+        assert!(Replay::decode(&format!("{}\nseed 1\nviewport 1280\n", HEADER)).is_err());
+        assert!(Replay::decode(&format!("{}\nseed 1\n16 0 v:1x\n", HEADER)).is_err());
+    }
+}
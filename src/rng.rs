@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+/// 本局使用的随机种子, `fixed` 不为空时每局都使用同一个种子
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RunSeed {
+    pub fixed: Option<u64>,
+    pub current: u64,
+}
+
+impl RunSeed {
+    pub fn fixed(seed: u64) -> Self {
+        RunSeed {
+            fixed: Some(seed),
+            current: seed,
+        }
+    }
+}
+
+/// 每个子系统各自独立的随机数流, 一个系统多消耗随机数不会影响其他系统的结果
+#[derive(Resource, Debug, Clone)]
+pub struct RngStreams {
+    pub spawning: SmallRng,
+    pub loot: SmallRng,
+    pub upgrades: SmallRng,
+    pub combat: SmallRng,
+}
+
+impl RngStreams {
+    pub fn new(seed: u64) -> Self {
+        // 用不同的常数把同一个种子分成互不相关的流
+        let stream =
+            |index: u64| SmallRng::seed_from_u64(seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        RngStreams {
+            spawning: stream(1),
+            loot: stream(2),
+            upgrades: stream(3),
+            combat: stream(4),
+        }
+    }
+}
+
+impl Default for RngStreams {
+    fn default() -> Self {
+        RngStreams::new(0)
+    }
+}
@@ -0,0 +1,307 @@
+use std::io;
+
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::components::SettingsButton;
+use crate::gameplay::GameSet;
+use crate::profile::config_path;
+use crate::states::AppState;
+
+const SETTINGS_FILE: &str = "settings.toml";
+// 设置文件的格式有变化时加一, 并在 `migrate` 中处理旧的格式
+const SETTINGS_VERSION: i64 = 1;
+// 背景音乐在音量设置为 100% 时的实际音量
+const MUSIC_LEVEL: f32 = 0.2;
+
+/// 读取和保存设置, 并把窗口相关的设置应用到主窗口
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_systems(
+                Update,
+                click_settings_button
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(AppState::Title).or_else(in_state(AppState::Paused))),
+            )
+            .add_systems(
+                Update,
+                (apply_window_settings, save_settings)
+                    .in_set(GameSet::Presentation)
+                    .run_if(resource_changed::<Settings>()),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "zh")]
+    Chinese,
+    #[serde(rename = "en")]
+    English,
+}
+
+impl Language {
+    /// 按当前语言选择界面文字
+    pub fn pick<'a>(&self, zh: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Language::Chinese => zh,
+            Language::English => en,
+        }
+    }
+
+    /// 按当前语言选择数据文件中的文字, 没有填写英文时使用中文
+    pub fn pick_data<'a>(&self, zh: &'a str, en: Option<&'a str>) -> &'a str {
+        match (self, en) {
+            (Language::English, Some(en)) => en,
+            _ => zh,
+        }
+    }
+
+    pub fn next(&self) -> Language {
+        match self {
+            Language::Chinese => Language::English,
+            Language::English => Language::Chinese,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Chinese => "中文",
+            Language::English => "English",
+        }
+    }
+}
+
+/// 保存在平台配置目录中的设置, 音量为 0 到 1
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: i64,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub screen_shake: bool,
+    pub damage_numbers: bool,
+    pub language: Language,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            master_volume: 1.0,
+            music_volume: 0.5,
+            sfx_volume: 1.0,
+            fullscreen: false,
+            vsync: true,
+            screen_shake: true,
+            damage_numbers: true,
+            language: Language::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// 读取失败时使用默认设置, 旧版本的文件会先迁移到当前版本
+    pub fn load() -> Settings {
+        let Some(path) = config_path(SETTINGS_FILE) else {
+            return Settings::default();
+        };
+        let s = match std::fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Settings::default(),
+            Err(err) => {
+                warn!("failed to read settings {}: {}", path.display(), err);
+                return Settings::default();
+            }
+        };
+        match Settings::decode(&s) {
+            Ok(settings) => settings,
+            Err(err) => {
+                warn!("ignoring invalid settings {}: {}", path.display(), err);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn decode(s: &str) -> Result<Settings, String> {
+        let mut table: toml::Table = toml::from_str(s).map_err(|err| err.to_string())?;
+        migrate(&mut table)?;
+        table
+            .try_into()
+            .map_err(|err: toml::de::Error| err.to_string())
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_path(SETTINGS_FILE) else {
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            .and_then(|s| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&path, s)
+            });
+        if let Err(err) = result {
+            error!("failed to save settings to {}: {}", path.display(), err);
+        }
+    }
+
+    /// 背景音乐播放时的实际音量
+    pub fn music_level(&self) -> f32 {
+        self.master_volume * self.music_volume * MUSIC_LEVEL
+    }
+
+    /// 音效播放时的实际音量
+    pub fn sfx_level(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+}
+
+/// 逐个版本升级设置文件, 没有版本号的文件为版本 0
+fn migrate(table: &mut toml::Table) -> Result<(), String> {
+    let mut version = table
+        .get("version")
+        .and_then(|version| version.as_integer())
+        .unwrap_or(0);
+    if version > SETTINGS_VERSION {
+        return Err(format!("unsupported settings version {}", version));
+    }
+    while version < SETTINGS_VERSION {
+        match version {
+            // 版本 0 只有一个音量, 直接是背景音乐播放时的音量, 范围 0 到 3.
+            // 换算成主音量为 100% 时的音乐音量, 超过现在最大音量的部分按最大值处理
+            0 => {
+                let volume = table.remove("volume").and_then(|volume| {
+                    volume
+                        .as_float()
+                        .or_else(|| volume.as_integer().map(|volume| volume as f64))
+                });
+                if let Some(volume) = volume {
+                    let mut music_volume = (volume as f32 / MUSIC_LEVEL).min(1f32);
+                    step_volume(&mut music_volume, 0f32);
+                    table.insert(
+                        "music_volume".to_owned(),
+                        toml::Value::Float(music_volume as f64),
+                    );
+                }
+            }
+            _ => return Err(format!("unknown settings version {}", version)),
+        }
+        version += 1;
+        info!("migrated settings to version {}", version);
+    }
+    table.insert("version".to_owned(), toml::Value::Integer(version));
+    Ok(())
+}
+
+/// 按 10% 调整音量, 避免多次加减后出现 0.30000001 这样的值
+pub fn step_volume(volume: &mut f32, change: f32) {
+    *volume = ((*volume + change) * 10f32).round().clamp(0f32, 10f32) / 10f32;
+}
+
+pub fn click_settings_button(
+    interaction: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, button) in &interaction {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            SettingsButton::MasterVolume(change) => {
+                step_volume(&mut settings.master_volume, *change)
+            }
+            SettingsButton::MusicVolume(change) => step_volume(&mut settings.music_volume, *change),
+            SettingsButton::SfxVolume(change) => step_volume(&mut settings.sfx_volume, *change),
+            SettingsButton::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsButton::Vsync => settings.vsync = !settings.vsync,
+            SettingsButton::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsButton::DamageNumbers => settings.damage_numbers = !settings.damage_numbers,
+            SettingsButton::Language => settings.language = settings.language.next(),
+        }
+    }
+}
+
+pub fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    let mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+pub fn save_settings(settings: Res<Settings>) {
+    // 启动时读取的设置不需要马上写回
+    if !settings.is_added() {
+        settings.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_current_version() {
+        let settings = Settings {
+            music_volume: 0.3,
+            sfx_volume: 0.7,
+            screen_shake: false,
+            language: Language::English,
+            ..Default::default()
+        };
+        let s = toml::to_string(&settings).unwrap();
+        assert_eq!(Settings::decode(&s), Ok(settings));
+    }
+
+    #[test]
+    fn migrate_version_0_volume() {
+        let settings = Settings::decode("volume = 0.1").unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.master_volume, 1.0);
+        assert_eq!(settings.music_volume, 0.5);
+        assert_eq!(settings.sfx_volume, 1.0);
+        assert!((settings.music_level() - 0.1).abs() < 1e-6);
+
+        let settings = Settings::decode("volume = 3").unwrap();
+        assert_eq!(settings.music_volume, 1.0);
+
+        let settings = Settings::decode("").unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        assert!(Settings::decode("volume = ").is_err());
+        assert!(Settings::decode("master_volume = \"loud\"").is_err());
+        assert!(Settings::decode("version = 99").is_err());
+        assert!(Settings::decode("version = -1").is_err());
+    }
+}
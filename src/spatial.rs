@@ -0,0 +1,289 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+const CELL_SIZE: f32 = 64.0;
+
+/// 按网格划分的位置索引, 每帧重建一次, 用于快速查找附近带有 `T` 组件的实体
+pub struct SpatialIndex<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+    // 有实体的格子范围, 查找时不会超出这个范围
+    bounds: Option<(IVec2, IVec2)>,
+    len: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Resource for SpatialIndex<T> {}
+
+impl<T> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        SpatialIndex::new(CELL_SIZE)
+    }
+}
+
+impl<T> SpatialIndex<T> {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialIndex {
+            cell_size,
+            cells: HashMap::default(),
+            bounds: None,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        // 上一帧就没有实体的格子直接移除, 避免格子越积越多
+        self.cells.retain(|_, entries| {
+            let occupied = !entries.is_empty();
+            entries.clear();
+            occupied
+        });
+        self.bounds = None;
+        self.len = 0;
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => (min.min(cell), max.max(cell)),
+            None => (cell, cell),
+        });
+        self.len += 1;
+    }
+
+    /// 距离 `point` 不超过 `radius` 的所有实体, 顺序不定
+    pub fn within(&self, point: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let (mut min, mut max) = (
+            self.cell(point - Vec2::splat(radius)),
+            self.cell(point + Vec2::splat(radius)),
+        );
+        if let Some((low, high)) = self.bounds {
+            min = min.max(low);
+            max = max.min(high);
+        } else {
+            max = min - IVec2::ONE;
+        }
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, position)| position.distance_squared(point) <= radius * radius)
+    }
+
+    /// 离 `point` 最近的至多 `k` 个实体, 由近到远排列
+    pub fn nearest(&self, point: Vec2, k: usize) -> Vec<(Entity, Vec2)> {
+        let Some((low, high)) = self.bounds else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+        let center = self.cell(point);
+        // 超过这一圈就不会再有实体
+        let max_ring = [
+            center.x - low.x,
+            high.x - center.x,
+            center.y - low.y,
+            high.y - center.y,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+        .max(0);
+
+        let mut candidates: Vec<(f32, Entity, Vec2)> = Vec::new();
+        for ring in 0..=max_ring {
+            for cell in ring_cells(center, ring) {
+                if let Some(entries) = self.cells.get(&cell) {
+                    candidates.extend(entries.iter().map(|(entity, position)| {
+                        (position.distance_squared(point), *entity, *position)
+                    }));
+                }
+            }
+            if candidates.len() >= k {
+                candidates.select_nth_unstable_by(k - 1, |l, r| l.0.total_cmp(&r.0));
+                candidates.truncate(k);
+                // 外圈的实体至少相距 ring 个格子, 已经不可能更近
+                let reach = ring as f32 * self.cell_size;
+                if candidates
+                    .iter()
+                    .all(|(distance, ..)| *distance <= reach * reach)
+                {
+                    break;
+                }
+            }
+        }
+        candidates.sort_unstable_by(|l, r| l.0.total_cmp(&r.0));
+        candidates
+            .into_iter()
+            .map(|(_, entity, position)| (entity, position))
+            .collect()
+    }
+}
+
+// 与中心格子切比雪夫距离恰好为 ring 的格子
+fn ring_cells(center: IVec2, ring: i32) -> Vec<IVec2> {
+    if ring == 0 {
+        return vec![center];
+    }
+    let mut cells = Vec::with_capacity(ring as usize * 8);
+    for x in -ring..=ring {
+        cells.push(center + IVec2::new(x, -ring));
+        cells.push(center + IVec2::new(x, ring));
+    }
+    for y in 1 - ring..ring {
+        cells.push(center + IVec2::new(-ring, y));
+        cells.push(center + IVec2::new(ring, y));
+    }
+    cells
+}
+
+pub fn update_spatial_index<T: Component>(
+    mut index: ResMut<SpatialIndex<T>>,
+    entities: Query<(Entity, &Transform), With<T>>,
+) {
+    index.clear();
+    for (entity, transform) in &entities {
+        index.insert(entity, transform.translation.truncate());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Marker;
+
+    fn index(points: &[Vec2]) -> SpatialIndex<Marker> {
+        let mut index = SpatialIndex::new(CELL_SIZE);
+        for (i, point) in points.iter().enumerate() {
+            index.insert(Entity::from_raw(i as u32), *point);
+        }
+        index
+    }
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort();
+        entities
+    }
+
+    // 逐个比较距离的结果, 用来对照索引的查找
+    fn brute_within(points: &[Vec2], point: Vec2, radius: f32) -> Vec<Entity> {
+        (0..points.len())
+            .filter(|&i| points[i].distance_squared(point) <= radius * radius)
+            .map(|i| Entity::from_raw(i as u32))
+            .collect()
+    }
+
+    fn brute_nearest_distances(points: &[Vec2], point: Vec2, k: usize) -> Vec<f32> {
+        let mut distances: Vec<f32> = points.iter().map(|p| p.distance(point)).collect();
+        distances.sort_unstable_by(f32::total_cmp);
+        distances.truncate(k);
+        distances
+    }
+
+    #[test]
+    fn empty_index() {
+        let empty = index(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.within(Vec2::ZERO, 1000f32).count(), 0);
+        assert!(empty.nearest(Vec2::ZERO, 5).is_empty());
+
+        // 清空之后与新建的索引相同
+        let mut cleared = index(&[Vec2::ONE]);
+        cleared.clear();
+        assert!(cleared.is_empty());
+        assert_eq!(cleared.within(Vec2::ONE, 10f32).count(), 0);
+        assert!(cleared.nearest(Vec2::ONE, 1).is_empty());
+    }
+
+    #[test]
+    fn points_on_cell_borders() {
+        // 正好落在格子边界和半径边界上的点
+        let points = [
+            Vec2::ZERO,
+            Vec2::new(CELL_SIZE, 0f32),
+            Vec2::new(0f32, -CELL_SIZE),
+            Vec2::new(CELL_SIZE, CELL_SIZE),
+            Vec2::new(-CELL_SIZE, -CELL_SIZE),
+        ];
+        let index = index(&points);
+        for point in points {
+            for radius in [0f32, CELL_SIZE, CELL_SIZE * std::f32::consts::SQRT_2] {
+                let found = index.within(point, radius).map(|(entity, _)| entity);
+                assert_eq!(
+                    sorted(found.collect()),
+                    brute_within(&points, point, radius),
+                    "within({}, {})",
+                    point,
+                    radius
+                );
+            }
+        }
+        assert_eq!(
+            index.nearest(Vec2::new(CELL_SIZE, 0f32), 1),
+            [(Entity::from_raw(1), Vec2::new(CELL_SIZE, 0f32))]
+        );
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let points: Vec<Vec2> = (0..50)
+            .map(|i| Vec2::new(-1000f32 + i as f32 * 37.5, -20f32 - i as f32 * 11.3))
+            .collect();
+        let index = index(&points);
+        for point in [
+            Vec2::new(-500f32, -300f32),
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(-2000f32, 100f32),
+        ] {
+            let found = index.within(point, 200f32).map(|(entity, _)| entity);
+            assert_eq!(
+                sorted(found.collect()),
+                brute_within(&points, point, 200f32)
+            );
+            let nearest: Vec<f32> = index
+                .nearest(point, 7)
+                .iter()
+                .map(|(_, position)| position.distance(point))
+                .collect();
+            assert_eq!(nearest, brute_nearest_distances(&points, point, 7));
+        }
+    }
+
+    #[test]
+    fn k_larger_than_population() {
+        let points = [
+            Vec2::new(10f32, 10f32),
+            Vec2::new(-300f32, 40f32),
+            Vec2::new(500f32, -700f32),
+        ];
+        let index = index(&points);
+        let nearest = index.nearest(Vec2::ZERO, 10);
+        assert_eq!(
+            nearest
+                .iter()
+                .map(|(entity, _)| *entity)
+                .collect::<Vec<_>>(),
+            [0, 1, 2].map(Entity::from_raw)
+        );
+        assert!(index.nearest(Vec2::ZERO, 0).is_empty());
+    }
+}
@@ -4,9 +4,11 @@ use bevy::prelude::States;
 pub enum AppState {
     #[default]
     Loading,
+    Title,
     Tutorial,
     Start,
     InGame,
     GameOver,
     SelectUpgrade,
+    Paused,
 }
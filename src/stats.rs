@@ -0,0 +1,350 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::components::{Bullet, Health, MaxHealth, MoveSpeed, PickupRadius, Player};
+use crate::events::PropsUpdateEvent;
+use crate::settings::Language;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
+    MaxHealth,
+    MoveSpeed,
+    // 对所有武器生效的倍率, 子弹数量为额外增加的数量
+    FireRate,
+    BulletSpeed,
+    BulletDamage,
+    TargetCount,
+    PickupRadius,
+    // 以下为环绕, 光环和冲击波武器的属性, 数量, 范围和频率为 0 时武器未解锁
+    OrbitCount,
+    OrbitDamage,
+    AuraRadius,
+    AuraDamage,
+    ShockwaveRate,
+    ShockwaveDamage,
+    ShockwaveRadius,
+    // 以下主要来自被动道具, 冷却, 范围, 持续时间和幸运为倍率
+    Armor,
+    Regen,
+    Cooldown,
+    Area,
+    Duration,
+    Luck,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 20] = [
+        Stat::MaxHealth,
+        Stat::MoveSpeed,
+        Stat::FireRate,
+        Stat::BulletSpeed,
+        Stat::BulletDamage,
+        Stat::TargetCount,
+        Stat::PickupRadius,
+        Stat::OrbitCount,
+        Stat::OrbitDamage,
+        Stat::AuraRadius,
+        Stat::AuraDamage,
+        Stat::ShockwaveRate,
+        Stat::ShockwaveDamage,
+        Stat::ShockwaveRadius,
+        Stat::Armor,
+        Stat::Regen,
+        Stat::Cooldown,
+        Stat::Area,
+        Stat::Duration,
+        Stat::Luck,
+    ];
+
+    pub fn label(&self, language: Language) -> &'static str {
+        let (zh, en) = match self {
+            Stat::MaxHealth => ("生命上限", "Max health"),
+            Stat::MoveSpeed => ("移动速度", "Move speed"),
+            Stat::FireRate => ("射速", "Fire rate"),
+            Stat::BulletSpeed => ("弹速", "Bullet speed"),
+            Stat::BulletDamage => ("子弹伤害", "Bullet damage"),
+            Stat::TargetCount => ("子弹数量", "Bullet count"),
+            Stat::PickupRadius => ("拾取范围", "Pickup radius"),
+            Stat::OrbitCount => ("旋刃数量", "Orbit count"),
+            Stat::OrbitDamage => ("旋刃伤害", "Orbit damage"),
+            Stat::AuraRadius => ("光环范围", "Aura radius"),
+            Stat::AuraDamage => ("光环伤害", "Aura damage"),
+            Stat::ShockwaveRate => ("冲击波频率", "Shockwave rate"),
+            Stat::ShockwaveDamage => ("冲击波伤害", "Shockwave damage"),
+            Stat::ShockwaveRadius => ("冲击波范围", "Shockwave radius"),
+            Stat::Armor => ("护甲", "Armor"),
+            Stat::Regen => ("再生", "Regen"),
+            Stat::Cooldown => ("冷却", "Cooldown"),
+            Stat::Area => ("范围", "Area"),
+            Stat::Duration => ("持续时间", "Duration"),
+            Stat::Luck => ("幸运", "Luck"),
+        };
+        language.pick(zh, en)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Add,
+    Multiply,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ModifierSource {
+    Upgrade(String),
+    Item(String),
+    Character(String),
+    Buff(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Modifier {
+    pub stat: Stat,
+    pub op: Op,
+    pub value: f32,
+    pub source: ModifierSource,
+    // 临时增益到期后自动移除
+    pub duration: Option<Timer>,
+}
+
+impl Modifier {
+    pub fn new(stat: Stat, op: Op, value: f32, source: ModifierSource) -> Self {
+        Modifier {
+            stat,
+            op,
+            value,
+            source,
+            duration: None,
+        }
+    }
+
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = Some(Timer::from_seconds(seconds, TimerMode::Once));
+        self
+    }
+}
+
+/// 基础属性加上各个来源的修正, 实际数值为 `(基础值 + 加法修正之和) * 乘法修正之积`
+#[derive(Component, Debug, Default)]
+pub struct Stats {
+    base: HashMap<Stat, f32>,
+    modifiers: Vec<Modifier>,
+}
+
+impl Stats {
+    pub fn new(base: impl IntoIterator<Item = (Stat, f32)>) -> Self {
+        Stats {
+            base: base.into_iter().collect(),
+            modifiers: Vec::new(),
+        }
+    }
+
+    pub fn base(&self, stat: Stat) -> f32 {
+        self.base.get(&stat).copied().unwrap_or(0f32)
+    }
+
+    pub fn get(&self, stat: Stat) -> f32 {
+        let (add, multiply) = self
+            .modifiers
+            .iter()
+            .filter(|modifier| modifier.stat == stat)
+            .fold((0f32, 1f32), |(add, multiply), modifier| {
+                match modifier.op {
+                    Op::Add => (add + modifier.value, multiply),
+                    Op::Multiply => (add, multiply * modifier.value),
+                }
+            });
+        (self.base(stat) + add) * multiply
+    }
+
+    pub fn add_modifier(&mut self, modifier: Modifier) {
+        self.modifiers.push(modifier);
+    }
+
+    pub fn remove_source(&mut self, source: &ModifierSource) {
+        self.modifiers.retain(|modifier| &modifier.source != source);
+    }
+
+    pub fn components(&self) -> (MaxHealth, MoveSpeed, PickupRadius) {
+        (
+            MaxHealth(self.get(Stat::MaxHealth)),
+            MoveSpeed(self.get(Stat::MoveSpeed)),
+            PickupRadius(self.get(Stat::PickupRadius)),
+        )
+    }
+}
+
+pub fn tick_stat_modifiers(time: Res<Time>, mut stats: Query<&mut Stats>) {
+    for mut stats in &mut stats {
+        let mut expired = false;
+        for modifier in &mut stats.bypass_change_detection().modifiers {
+            if let Some(duration) = &mut modifier.duration {
+                expired |= duration.tick(time.delta()).finished();
+            }
+        }
+        if expired {
+            stats.modifiers.retain(|modifier| {
+                modifier
+                    .duration
+                    .as_ref()
+                    .map_or(true, |duration| !duration.finished())
+            });
+        }
+    }
+}
+
+pub fn apply_stats(
+    mut players: Query<
+        (
+            &Stats,
+            &mut Health,
+            &mut MaxHealth,
+            &mut MoveSpeed,
+            &mut PickupRadius,
+        ),
+        (With<Player>, Without<Bullet>, Changed<Stats>),
+    >,
+    mut ev_props_update: EventWriter<PropsUpdateEvent>,
+) {
+    for (stats, mut health, mut max_health, mut move_speed, mut pickup_radius) in &mut players {
+        let (new_max_health, new_move_speed, new_pickup_radius) = stats.components();
+        // 提升上限的同时补上增加的部分
+        if new_max_health.0 > max_health.0 {
+            health.0 += new_max_health.0 - max_health.0;
+        }
+        health.0 = health.0.min(new_max_health.0);
+        *max_health = new_max_health;
+        *move_speed = new_move_speed;
+        *pickup_radius = new_pickup_radius;
+        ev_props_update.send(PropsUpdateEvent);
+    }
+}
+
+pub fn regenerate_health(
+    time: Res<Time>,
+    mut players: Query<(&Stats, &mut Health, &MaxHealth), With<Player>>,
+    mut ev_props_update: EventWriter<PropsUpdateEvent>,
+) {
+    for (stats, mut health, max_health) in &mut players {
+        let regen = stats.get(Stat::Regen);
+        if regen <= 0f32 || health.0 >= max_health.0 {
+            continue;
+        }
+        let before = health.0;
+        health.0 = (health.0 + regen * time.delta_seconds()).min(max_health.0);
+        // 显示的是整数, 跨过整数时才刷新
+        if health.0.floor() != before.floor() {
+            ev_props_update.send(PropsUpdateEvent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn buff(id: &str) -> ModifierSource {
+        ModifierSource::Buff(id.to_owned())
+    }
+
+    fn tick(world: &mut World, seconds: f32) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(tick_stat_modifiers);
+    }
+
+    #[test]
+    fn adds_before_multiplying() {
+        let mut stats = Stats::new([(Stat::MoveSpeed, 100f32)]);
+        stats.add_modifier(Modifier::new(
+            Stat::MoveSpeed,
+            Op::Multiply,
+            1.5,
+            ModifierSource::Character("hero".to_owned()),
+        ));
+        stats.add_modifier(Modifier::new(
+            Stat::MoveSpeed,
+            Op::Add,
+            20f32,
+            ModifierSource::Upgrade("boots".to_owned()),
+        ));
+        stats.add_modifier(Modifier::new(
+            Stat::MoveSpeed,
+            Op::Multiply,
+            2f32,
+            ModifierSource::Item("wings".to_owned()),
+        ));
+        // 其他属性的修正不受影响
+        stats.add_modifier(Modifier::new(
+            Stat::Armor,
+            Op::Add,
+            3f32,
+            ModifierSource::Item("plate".to_owned()),
+        ));
+
+        assert_eq!(stats.base(Stat::MoveSpeed), 100f32);
+        assert_eq!(stats.get(Stat::MoveSpeed), (100f32 + 20f32) * 1.5 * 2f32);
+        assert_eq!(stats.get(Stat::Armor), 3f32);
+        assert_eq!(stats.get(Stat::Luck), 0f32);
+    }
+
+    #[test]
+    fn timed_buffs_expire() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let mut stats = Stats::new([(Stat::Armor, 1f32)]);
+        stats.add_modifier(
+            Modifier::new(Stat::Armor, Op::Add, 5f32, buff("shield")).with_duration(1f32),
+        );
+        stats.add_modifier(Modifier::new(
+            Stat::Armor,
+            Op::Add,
+            2f32,
+            ModifierSource::Item("plate".to_owned()),
+        ));
+        let entity = world.spawn(stats).id();
+
+        tick(&mut world, 0.5);
+        assert_eq!(world.get::<Stats>(entity).unwrap().get(Stat::Armor), 8f32);
+
+        // 到期后只移除临时增益, 永久修正保留
+        tick(&mut world, 0.6);
+        let stats = world.get::<Stats>(entity).unwrap();
+        assert_eq!(stats.get(Stat::Armor), 3f32);
+        assert_eq!(stats.modifiers.len(), 1);
+    }
+
+    #[test]
+    fn refreshing_a_buff_replaces_it() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let mut stats = Stats::default();
+        stats.add_modifier(
+            Modifier::new(Stat::Luck, Op::Add, 1f32, buff("clover")).with_duration(1f32),
+        );
+        let entity = world.spawn(stats).id();
+        tick(&mut world, 0.8);
+
+        // 重新获得同一来源的增益时先移除旧的, 持续时间从头计算
+        let mut stats = world.get_mut::<Stats>(entity).unwrap();
+        stats.remove_source(&buff("clover"));
+        stats.add_modifier(
+            Modifier::new(Stat::Luck, Op::Add, 1f32, buff("clover")).with_duration(1f32),
+        );
+        assert_eq!(stats.get(Stat::Luck), 1f32);
+        assert_eq!(stats.modifiers.len(), 1);
+
+        tick(&mut world, 0.8);
+        assert_eq!(world.get::<Stats>(entity).unwrap().get(Stat::Luck), 1f32);
+        tick(&mut world, 0.3);
+        assert_eq!(world.get::<Stats>(entity).unwrap().get(Stat::Luck), 0f32);
+    }
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::assets::{FontAssets, Killed, RunClock};
+use crate::characters::{CharacterSelection, SelectedCharacter};
+use crate::components::{Level, Player, TitleButton, TitleUI, UIButton};
+use crate::events::StartEvent;
+use crate::gameplay::GameSet;
+use crate::profile::Profile;
+use crate::settings::Settings;
+use crate::states::AppState;
+use crate::tutorial::TutorialFromTitle;
+use crate::ui::{spawn_settings_panel, spawn_text_button};
+
+/// 标题画面: 开始游戏, 选择角色, 设置, 统计和制作人员.
+/// 第一次启动时先显示教程, 之后只在菜单中选择时显示.
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartEvent>()
+            .insert_resource(Profile::load())
+            .init_resource::<TitleMenu>()
+            .init_resource::<Settings>()
+            .init_resource::<TutorialFromTitle>()
+            .add_systems(OnEnter(AppState::Title), enter_title)
+            .add_systems(OnExit(AppState::Title), exit_title_ui)
+            .add_systems(OnExit(AppState::Tutorial), mark_tutorial_seen)
+            .add_systems(OnEnter(AppState::GameOver), record_run)
+            .add_systems(
+                Update,
+                (
+                    (click_title_button, start_from_title).in_set(GameSet::Input),
+                    title_ui.in_set(GameSet::Presentation).run_if(
+                        resource_changed::<TitleMenu>().or_else(resource_changed::<Settings>()),
+                    ),
+                )
+                    .run_if(in_state(AppState::Title)),
+            );
+    }
+}
+
+/// 标题画面当前显示的页面
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TitleMenu {
+    #[default]
+    Main,
+    Characters,
+    Settings,
+    Statistics,
+    Credits,
+}
+
+pub fn enter_title(
+    profile: Res<Profile>,
+    mut menu: ResMut<TitleMenu>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut ev_start: ResMut<Events<StartEvent>>,
+) {
+    // 关闭教程时的事件还没有过期, 不能让它直接开始游戏
+    ev_start.clear();
+    if !profile.tutorial_seen {
+        next_state.set(AppState::Tutorial);
+        return;
+    }
+    *menu = TitleMenu::Main;
+}
+
+pub fn title_ui(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    menu: Res<TitleMenu>,
+    profile: Res<Profile>,
+    settings: Res<Settings>,
+    characters: CharacterSelection,
+    old: Query<Entity, With<TitleUI>>,
+) {
+    for entity in &old {
+        commands.entity(entity).despawn_recursive();
+    }
+    let language = settings.language;
+    let character = characters.get();
+    let text = |value: String, font_size: f32| {
+        (
+            TextBundle::from_section(
+                value,
+                TextStyle {
+                    font: font_assets.chs.clone(),
+                    font_size,
+                    ..Default::default()
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::all(Val::Px(5f32)),
+                ..Default::default()
+            })
+            .with_text_alignment(TextAlignment::Center),
+            Label,
+        )
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            TitleUI,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle::from_section(
+                    "Vector Survivor",
+                    TextStyle {
+                        font: font_assets.eng.clone(),
+                        font_size: 60.0,
+                        ..Default::default()
+                    },
+                ),
+                Label,
+            ));
+            match *menu {
+                TitleMenu::Main => {
+                    if let Some(character) = character {
+                        let label = language.pick("角色", "Character");
+                        builder.spawn(text(format!("{}: {}", label, character.localized_name(language)), 20.0));
+                    }
+                    spawn_text_button(
+                        builder,
+                        font_assets.chs.clone(),
+                        UIButton::Start,
+                        language.pick("开始", "Play").to_owned(),
+                        Color::WHITE,
+                    );
+                    for (button, label) in [
+                        (
+                            TitleButton::Characters,
+                            language.pick("选择角色", "Characters"),
+                        ),
+                        (TitleButton::Tutorial, language.pick("教程", "Tutorial")),
+                        (TitleButton::Settings, language.pick("设置", "Settings")),
+                        (TitleButton::Statistics, language.pick("统计", "Statistics")),
+                        (TitleButton::Credits, language.pick("制作人员", "Credits")),
+                        (TitleButton::Quit, language.pick("退出", "Quit")),
+                    ] {
+                        spawn_text_button(
+                            builder,
+                            font_assets.chs.clone(),
+                            button,
+                            label.to_owned(),
+                            Color::WHITE,
+                        );
+                    }
+                }
+                TitleMenu::Characters => {
+                    for option in characters.all() {
+                        let chosen = character.is_some_and(|character| character.id == option.id);
+                        spawn_text_button(
+                            builder,
+                            font_assets.chs.clone(),
+                            TitleButton::Character(option.id.clone()),
+                            format!(
+                                "{}\n{}",
+                                option.localized_name(language),
+                                option.localized_description(language)
+                            ),
+                            if chosen { Color::GOLD } else { Color::WHITE },
+                        );
+                    }
+                }
+                TitleMenu::Settings => {
+                    spawn_settings_panel(builder, font_assets.chs.clone(), &settings);
+                }
+                TitleMenu::Statistics => {
+                    let best_time = Duration::from_secs(profile.best_time as u64);
+                    builder.spawn(text(
+                        format!(
+                            "{}: {}\n{}: {}\n{}: {:?}\n{}: {}",
+                            language.pick("游戏局数", "Runs"),
+                            profile.runs,
+                            language.pick("击败敌人", "Enemies killed"),
+                            profile.total_kills,
+                            language.pick("最长存活", "Longest run"),
+                            best_time,
+                            language.pick("最高等级", "Highest level"),
+                            profile.best_level
+                        ),
+                        30.0,
+                    ));
+                }
+                TitleMenu::Credits => {
+                    builder.spawn(text(
+                        language
+                            .pick(
+                                "字体: ZCOOL KuaiLe, JetBrains Mono\n音乐: Hills of Radiant Wind\n使用 Bevy 制作",
+                                "Fonts: ZCOOL KuaiLe, JetBrains Mono\nMusic: Hills of Radiant Wind\nMade with Bevy",
+                            )
+                            .to_owned(),
+                        30.0,
+                    ));
+                }
+            }
+            if *menu != TitleMenu::Main {
+                spawn_text_button(
+                    builder,
+                    font_assets.chs.clone(),
+                    TitleButton::Back,
+                    language.pick("返回", "Back").to_owned(),
+                    Color::WHITE,
+                );
+            }
+        });
+}
+
+pub fn click_title_button(
+    interaction: Query<(&Interaction, &TitleButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut menu: ResMut<TitleMenu>,
+    mut selected: ResMut<SelectedCharacter>,
+    mut from_title: ResMut<TutorialFromTitle>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in &interaction {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            TitleButton::Characters => *menu = TitleMenu::Characters,
+            TitleButton::Character(id) => {
+                selected.0 = Some(id.clone());
+                *menu = TitleMenu::Main;
+            }
+            TitleButton::Tutorial => {
+                from_title.0 = true;
+                next_state.set(AppState::Tutorial);
+            }
+            TitleButton::Settings => *menu = TitleMenu::Settings,
+            TitleButton::Statistics => *menu = TitleMenu::Statistics,
+            TitleButton::Credits => *menu = TitleMenu::Credits,
+            TitleButton::Back => *menu = TitleMenu::Main,
+            TitleButton::Quit => ev_exit.send(AppExit),
+        }
+    }
+}
+
+pub fn start_from_title(
+    mut ev_start: EventReader<StartEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if ev_start.read().count() > 0 {
+        next_state.set(AppState::Start);
+    }
+}
+
+pub fn exit_title_ui(mut commands: Commands, entity: Query<Entity, With<TitleUI>>) {
+    entity.for_each(|e| commands.entity(e).despawn_recursive())
+}
+
+pub fn mark_tutorial_seen(mut profile: ResMut<Profile>) {
+    if !profile.tutorial_seen {
+        profile.tutorial_seen = true;
+        profile.save();
+    }
+}
+
+pub fn record_run(
+    mut profile: ResMut<Profile>,
+    clock: Res<RunClock>,
+    killed: Res<Killed>,
+    level: Query<&Level, With<Player>>,
+) {
+    profile.runs += 1;
+    profile.total_kills += killed.0 as u64;
+    profile.best_time = profile.best_time.max(clock.0.elapsed_secs());
+    if let Ok(level) = level.get_single() {
+        profile.best_level = profile.best_level.max(level.0);
+    }
+    profile.save();
+}
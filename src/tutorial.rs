@@ -1,11 +1,34 @@
-use crate::assets::{AudioAssets, FontAssets};
-use crate::components::{TutorialUI, UIButton, BGM};
+use crate::assets::FontAssets;
+use crate::components::{TutorialUI, UIButton};
 use crate::events::StartEvent;
+use crate::settings::Settings;
 use crate::states::AppState;
-use bevy::audio::{Volume, VolumeLevel};
 use bevy::prelude::*;
 
-pub fn setup_tutorial(mut commands: Commands, font_assets: Res<FontAssets>) {
+/// 加载完成后显示的教程页面, 按钮点击由 `UiPlugin` 处理
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartEvent>()
+            .init_resource::<Settings>()
+            .init_resource::<TutorialFromTitle>()
+            .add_systems(OnEnter(AppState::Tutorial), setup_tutorial)
+            .add_systems(Update, close_tutorial.run_if(in_state(AppState::Tutorial)));
+    }
+}
+
+/// 从标题画面的菜单打开教程时为 `true`, 关闭后回到标题画面而不是开始游戏
+#[derive(Resource, Debug, Default)]
+pub struct TutorialFromTitle(pub bool);
+
+pub fn setup_tutorial(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    settings: Res<Settings>,
+    from_title: Res<TutorialFromTitle>,
+) {
+    let language = settings.language;
     commands
         .spawn((
             NodeBundle {
@@ -26,7 +49,10 @@ pub fn setup_tutorial(mut commands: Commands, font_assets: Res<FontAssets>) {
         .with_children(|builder| {
             builder.spawn((
                 TextBundle::from_section(
-                    "使用W/A/S/D或者方向键来控制角色(中心圆点)的移动\n\",\"和\".\"号用于控制音量",
+                    language.pick(
+                        "使用W/A/S/D或者方向键来控制角色(中心圆点)的移动\n\",\"和\".\"号用于控制音量, Esc或P键暂停",
+                        "Move the player (the dot in the middle) with W/A/S/D or the arrow keys\n\",\" and \".\" change the volume, Esc or P pauses",
+                    ),
                     TextStyle {
                         font: font_assets.chs.clone(),
                         font_size: 40.0,
@@ -42,7 +68,10 @@ pub fn setup_tutorial(mut commands: Commands, font_assets: Res<FontAssets>) {
             ));
             builder.spawn((
                 TextBundle::from_section(
-                    "小心红色方块，要是被他们碰到就会当场去世！",
+                    language.pick(
+                        "小心红色方块，被他们碰到会损失生命值，生命值归零就会当场去世！",
+                        "Watch out for the red squares, touching them costs health and you die when it runs out!",
+                    ),
                     TextStyle {
                         font: font_assets.chs.clone(),
                         font_size: 40.0,
@@ -58,7 +87,10 @@ pub fn setup_tutorial(mut commands: Commands, font_assets: Res<FontAssets>) {
             ));
             builder.spawn((
                 TextBundle::from_section(
-                    "当底部绿色经验条满了之后，可以选择一项技能升级，每5级有额外升级项目。\n\n",
+                    language.pick(
+                        "击败敌人会掉落经验宝石，靠近就能拾取。\n当底部绿色经验条满了之后，可以选择一项技能升级，每5级有额外升级项目。\n\n",
+                        "Defeated enemies drop experience gems, walk close to pick them up.\nWhen the green bar at the bottom is full you can choose an upgrade, with extra choices every 5 levels.\n\n",
+                    ),
                     TextStyle {
                         font: font_assets.chs.clone(),
                         font_size: 40.0,
@@ -87,38 +119,15 @@ pub fn setup_tutorial(mut commands: Commands, font_assets: Res<FontAssets>) {
                         border_color: BorderColor(Color::WHITE),
                         ..Default::default()
                     },
-                    UIButton("tutorial:start"),
+                    UIButton::Start,
                 ))
                 .with_children(|builder| {
                     builder.spawn(TextBundle::from_section(
-                        "开始",
-                        TextStyle {
-                            font: font_assets.chs.clone(),
-                            font_size: 30.0,
-                            ..Default::default()
-                        },
-                    ));
-                });
-            builder
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            border: UiRect::all(Val::Px(2f32)),
-                            margin: UiRect::top(Val::Px(10f32)),
-                            padding: UiRect::all(Val::Px(5f32)),
-                            ..Default::default()
+                        if from_title.0 {
+                            language.pick("返回", "Back")
+                        } else {
+                            language.pick("开始", "Play")
                         },
-                        background_color: BackgroundColor(Color::NONE),
-                        border_color: BorderColor(Color::WHITE),
-                        ..Default::default()
-                    },
-                    UIButton("tutorial:start-without-bgm"),
-                ))
-                .with_children(|builder| {
-                    builder.spawn(TextBundle::from_section(
-                        "开始（无背景音乐）",
                         TextStyle {
                             font: font_assets.chs.clone(),
                             font_size: 30.0,
@@ -148,22 +157,17 @@ pub fn setup_tutorial(mut commands: Commands, font_assets: Res<FontAssets>) {
 pub fn close_tutorial(
     mut ev_start: EventReader<StartEvent>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut from_title: ResMut<TutorialFromTitle>,
     mut commands: Commands,
     ui: Query<Entity, With<TutorialUI>>,
-    audio_assets: Res<AudioAssets>,
 ) {
-    for bgm in ev_start.read() {
+    for _ in ev_start.read() {
         commands.entity(ui.single()).despawn_recursive();
-        next_state.set(AppState::Start);
-        if bgm.0 {
-            commands.spawn((
-                AudioBundle {
-                    source: audio_assets.bgm.clone(),
-                    settings: PlaybackSettings::LOOP
-                        .with_volume(Volume::Relative(VolumeLevel::new(0.1))),
-                },
-                BGM,
-            ));
-        }
+        next_state.set(if from_title.0 {
+            AppState::Title
+        } else {
+            AppState::Start
+        });
+        from_title.0 = false;
     }
 }
@@ -1,22 +1,235 @@
-use crate::assets::{FontAssets, GameTime};
+use crate::assets::{FontAssets, RunClock, Viewport};
+use bevy::pbr::ClusterConfig;
 use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::window::PrimaryWindow;
+use bevy_asset_loader::prelude::LoadingStateAppExt;
+use bevy_xpbd_2d::prelude::PhysicsSet;
 use std::time::Duration;
 
 use crate::components::{
-    Bullet, BulletSpeed, FireRate, GameEntity, GameOverUI, Level, MoveSpeed, Player, PlayerProps,
-    SelectUpgradeUI, StatsUI, StatsUIKill, StatsUITime, TargetCount, UIButton,
+    DamageNumber, GameEntity, GameOverUI, Health, Invulnerable, PauseButton, PauseUI, Player,
+    PlayerProps, SelectUpgradeUI, SettingsButton, StatsUI, StatsUIKill, StatsUITime, UIButton,
+    Weapon, WeaponLevel, WeaponStrip, XPBar,
 };
 use crate::events::{
-    PlayerBulletSpeedUpEvent, PlayerFireRateUpEvent, PlayerMoveSpeedUpEvent,
-    PlayerTargetCountUpEvent, PropsUpdateEvent, StartEvent,
+    BanishUpgradeEvent, DamageEvent, PropsUpdateEvent, RerollUpgradesEvent, SkipUpgradeEvent,
+    StartEvent, UpgradeActions, UpgradeSelectedEvent,
 };
+use crate::gameplay::GameSet;
+use crate::pause::PauseMenu;
+use crate::replay::ReplayPlayback;
+use crate::rng::RunSeed;
+use crate::settings::{Language, Settings};
 use crate::states::AppState;
+use crate::stats::{Stat, Stats};
+use crate::upgrades::{Offer, OfferSources, UpgradeOffers};
+use crate::weapons::WeaponDefs;
+
+const DAMAGE_NUMBER_SECONDS: f32 = 0.6;
+const DAMAGE_NUMBER_RISE: f32 = 30.0;
+const SHAKE_SECONDS: f32 = 0.25;
+const SHAKE_STRENGTH: f32 = 6.0;
+
+/// 玩家受伤时摄像机的震动, 剩余时间越少幅度越小
+#[derive(Resource, Debug, Default)]
+pub struct ScreenShake(pub Timer);
+
+/// 游戏界面和摄像机, 需要窗口和渲染
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartEvent>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(PreUpdate, sync_viewport)
+            .add_systems(
+                OnEnter(AppState::Start),
+                (show_properties, show_stats, show_weapon_strip),
+            )
+            .add_systems(
+                Update,
+                select_upgrade_ui
+                    .in_set(GameSet::Presentation)
+                    .run_if(in_state(AppState::SelectUpgrade))
+                    .run_if(resource_changed::<UpgradeOffers>()),
+            )
+            .add_systems(OnEnter(AppState::GameOver), game_over_ui)
+            .add_systems(
+                Update,
+                (
+                    click_pause_button.in_set(GameSet::Input),
+                    pause_menu_ui.in_set(GameSet::Presentation).run_if(
+                        resource_changed::<PauseMenu>().or_else(resource_changed::<Settings>()),
+                    ),
+                )
+                    .run_if(in_state(AppState::Paused)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_properties,
+                    update_time_stats,
+                    update_weapon_strip,
+                    (spawn_damage_numbers, float_damage_numbers).chain(),
+                    shake_on_hit,
+                )
+                    .in_set(GameSet::Presentation)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            // 摄像机要跟随物理同步之后的玩家位置
+            .add_systems(
+                PostUpdate,
+                camera_follow
+                    .after(PhysicsSet::Sync)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                click_button
+                    .in_set(GameSet::Input)
+                    .run_if(
+                        in_state(AppState::GameOver)
+                            .or_else(in_state(AppState::SelectUpgrade))
+                            .or_else(in_state(AppState::Tutorial))
+                            .or_else(in_state(AppState::Title)),
+                    )
+                    // 回放时升级选择来自录像
+                    .run_if(not(resource_exists::<ReplayPlayback>())),
+            )
+            .add_systems(OnExit(AppState::GameOver), exit_game_over_ui)
+            .add_systems(OnExit(AppState::SelectUpgrade), exit_select_upgrade_ui)
+            .add_systems(OnExit(AppState::Paused), exit_pause_ui)
+            .add_systems(OnEnter(AppState::Title), exit_stats_ui)
+            .init_resource::<ScreenShake>()
+            .init_resource::<PauseMenu>()
+            // 没有加入 SettingsPlugin 时使用默认设置
+            .init_resource::<Settings>()
+            .insert_resource(Msaa::Off)
+            .add_collection_to_loading_state::<_, FontAssets>(AppState::Loading);
+    }
+}
+
+pub fn setup_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                hdr: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ClusterConfig::FixedZ {
+            total: 1024,
+            z_slices: 1,
+            dynamic_resizing: true,
+            z_config: Default::default(),
+        },
+    ));
+}
+
+pub fn camera_follow(
+    players: Query<&Transform, With<Player>>,
+    mut cameras: Query<&mut Transform, (With<Camera>, Without<Player>)>,
+    viewport: Res<Viewport>,
+    mut bar: Query<&mut Transform, (With<XPBar>, Without<Player>, Without<Camera>)>,
+    mut shake: ResMut<ScreenShake>,
+    time: Res<Time>,
+) {
+    let transform = players.single();
+    let t = shake.0.tick(time.delta()).elapsed_secs();
+    let offset =
+        Vec2::new((t * 90f32).sin(), (t * 70f32).cos()) * SHAKE_STRENGTH * shake.0.percent_left();
+    for mut tf in &mut cameras {
+        tf.translation.x = transform.translation.x + offset.x;
+        tf.translation.y = transform.translation.y + offset.y;
+    }
+    let bar = &mut bar.single_mut().translation;
+    bar.x = transform.translation.x - viewport.0.x / 2f32;
+    bar.y = transform.translation.y - viewport.0.y / 2f32;
+}
+
+pub fn shake_on_hit(
+    players: Query<(), (With<Player>, Added<Invulnerable>)>,
+    settings: Res<Settings>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    if settings.screen_shake && !players.is_empty() {
+        shake.0 = Timer::from_seconds(SHAKE_SECONDS, TimerMode::Once);
+    }
+}
+
+pub fn spawn_damage_numbers(
+    mut commands: Commands,
+    mut ev_damage: EventReader<DamageEvent>,
+    settings: Res<Settings>,
+    font_assets: Res<FontAssets>,
+) {
+    if !settings.damage_numbers {
+        ev_damage.clear();
+        return;
+    }
+    for event in ev_damage.read() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("{:.0}", event.damage.max(1f32)),
+                    TextStyle {
+                        font: font_assets.eng.clone(),
+                        font_size: 14.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_translation(event.position.extend(10f32)),
+                ..Default::default()
+            },
+            GameEntity,
+            DamageNumber(Timer::from_seconds(DAMAGE_NUMBER_SECONDS, TimerMode::Once)),
+        ));
+    }
+}
+
+pub fn float_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut numbers: Query<(Entity, &mut DamageNumber, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut number, mut transform, mut text) in &mut numbers {
+        if number.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation.y += DAMAGE_NUMBER_RISE * time.delta_seconds();
+        let alpha = number.0.percent_left();
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+pub fn sync_viewport(window: Query<&Window, With<PrimaryWindow>>, mut viewport: ResMut<Viewport>) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let size = Vec2::new(window.width(), window.height());
+    if viewport.0 != size {
+        viewport.0 = size;
+    }
+}
 
 pub fn select_upgrade_ui(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
-    lvl: Query<&Level, With<Player>>,
+    sources: OfferSources,
+    offers: Res<UpgradeOffers>,
+    settings: Res<Settings>,
+    old: Query<Entity, With<SelectUpgradeUI>>,
 ) {
+    let language = settings.language;
+    for entity in &old {
+        commands.entity(entity).despawn_recursive();
+    }
     commands
         .spawn((
             NodeBundle {
@@ -35,7 +248,11 @@ pub fn select_upgrade_ui(
         .with_children(|builder| {
             builder.spawn((
                 TextBundle::from_section(
-                    "选择你的升级!",
+                    if offers.banishing {
+                        language.pick("选择要放逐的升级", "Choose an upgrade to banish")
+                    } else {
+                        language.pick("选择你的升级!", "Choose your upgrade!")
+                    },
                     TextStyle {
                         font: font_assets.chs.clone(),
                         font_size: 50.0,
@@ -53,7 +270,6 @@ pub fn select_upgrade_ui(
                 .spawn(NodeBundle {
                     style: Style {
                         width: Val::Percent(100.),
-                        height: Val::Percent(100.),
                         flex_direction: FlexDirection::Row,
                         align_items: AlignItems::Center,
                         justify_content: JustifyContent::Center,
@@ -62,46 +278,111 @@ pub fn select_upgrade_ui(
                     ..Default::default()
                 })
                 .with_children(|builder| {
-                    spawn_select_upgrade_ui_button(
-                        builder,
-                        font_assets.chs.clone(),
-                        "select_upgrade:move_speed",
-                        "移速升级",
-                        "\n\n玩家移动速度 +5%",
-                    );
-                    spawn_select_upgrade_ui_button(
+                    for offer in &offers.offers {
+                        let Some((name, description, color)) = sources.describe(offer, language)
+                        else {
+                            continue;
+                        };
+                        spawn_select_upgrade_ui_button(
+                            builder,
+                            font_assets.chs.clone(),
+                            offer,
+                            name,
+                            description,
+                            color,
+                        );
+                    }
+                    if offers.offers.is_empty() {
+                        spawn_text_button(
+                            builder,
+                            font_assets.chs.clone(),
+                            UIButton::SkipUpgrade,
+                            language.pick("继续", "Continue").to_owned(),
+                            Color::WHITE,
+                        );
+                    }
+                });
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|builder| {
+                    spawn_text_button(
                         builder,
                         font_assets.chs.clone(),
-                        "select_upgrade:fire_rate",
-                        "射速升级",
-                        "\n\n开火速率(每分钟) +5%",
+                        UIButton::Reroll,
+                        match language {
+                            Language::Chinese => format!("重新随机 (剩余{}次)", offers.rerolls),
+                            Language::English => format!("Reroll ({} left)", offers.rerolls),
+                        },
+                        Color::WHITE,
                     );
-                    spawn_select_upgrade_ui_button(
+                    spawn_text_button(
                         builder,
                         font_assets.chs.clone(),
-                        "select_upgrade:bullet_speed",
-                        "弹速升级",
-                        "\n\n子弹飞行速度 +5%",
+                        UIButton::Banish,
+                        match language {
+                            Language::Chinese => format!("放逐 (剩余{}次)", offers.banishes),
+                            Language::English => format!("Banish ({} left)", offers.banishes),
+                        },
+                        if offers.banishing {
+                            Color::RED
+                        } else {
+                            Color::WHITE
+                        },
                     );
-                    if lvl.single().0 % 5 == 0 {
-                        spawn_select_upgrade_ui_button(
-                            builder,
-                            font_assets.chs.clone(),
-                            "select_upgrade:target_count",
-                            "子弹数量升级",
-                            "\n\n可以同时射出的子弹 +1",
-                        );
-                    }
                 });
         });
 }
 
+pub fn spawn_text_button(
+    builder: &mut ChildBuilder,
+    font: Handle<Font>,
+    button: impl Component,
+    text: String,
+    border_color: Color,
+) {
+    builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    border: UiRect::all(Val::Px(2f32)),
+                    margin: UiRect::all(Val::Px(10f32)),
+                    padding: UiRect::all(Val::Px(5f32)),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::NONE),
+                border_color: BorderColor(border_color),
+                ..Default::default()
+            },
+            button,
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font,
+                    font_size: 20.0,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
 fn spawn_select_upgrade_ui_button(
     builder: &mut ChildBuilder,
     font: Handle<Font>,
-    key: &'static str,
-    text: &'static str,
-    desc: &'static str,
+    offer: &Offer,
+    name: String,
+    description: String,
+    border_color: Color,
 ) {
     builder
         .spawn((
@@ -115,16 +396,16 @@ fn spawn_select_upgrade_ui_button(
                     ..Default::default()
                 },
                 background_color: BackgroundColor(Color::NONE),
-                border_color: BorderColor(Color::WHITE),
+                border_color: BorderColor(border_color),
                 ..Default::default()
             },
-            UIButton(key),
+            UIButton::SelectUpgrade(offer.clone()),
         ))
         .with_children(|builder| {
             builder.spawn(
                 TextBundle::from_sections([
                     TextSection::new(
-                        text,
+                        name,
                         TextStyle {
                             font: font.clone(),
                             font_size: 30.0,
@@ -132,7 +413,7 @@ fn spawn_select_upgrade_ui_button(
                         },
                     ),
                     TextSection::new(
-                        desc,
+                        format!("\n\n{}", description),
                         TextStyle {
                             font,
                             font_size: 20.0,
@@ -145,7 +426,13 @@ fn spawn_select_upgrade_ui_button(
         });
 }
 
-pub fn game_over_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
+pub fn game_over_ui(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    seed: Res<RunSeed>,
+    settings: Res<Settings>,
+) {
+    let language = settings.language;
     commands
         .spawn((
             NodeBundle {
@@ -178,6 +465,45 @@ pub fn game_over_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
                 .with_text_alignment(TextAlignment::Center),
                 Label,
             ));
+            // 把种子分享出去就能重现这一局
+            builder.spawn((
+                TextBundle::from_section(
+                    format!("seed: {}", seed.current),
+                    TextStyle {
+                        font: font_assets.eng.clone(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                ),
+                Label,
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            border: UiRect::all(Val::Px(2f32)),
+                            margin: UiRect::top(Val::Px(10f32)),
+                            padding: UiRect::all(Val::Px(5f32)),
+                            ..Default::default()
+                        },
+                        background_color: BackgroundColor(Color::NONE),
+                        border_color: BorderColor(Color::WHITE),
+                        ..Default::default()
+                    },
+                    UIButton::Restart,
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        language.pick("重新开始", "Restart"),
+                        TextStyle {
+                            font: font_assets.chs.clone(),
+                            font_size: 30.0,
+                            ..Default::default()
+                        },
+                    ));
+                });
             builder
                 .spawn((
                     ButtonBundle {
@@ -193,11 +519,11 @@ pub fn game_over_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
                         border_color: BorderColor(Color::WHITE),
                         ..Default::default()
                     },
-                    UIButton("game_over:restart"),
+                    UIButton::Title,
                 ))
                 .with_children(|builder| {
                     builder.spawn(TextBundle::from_section(
-                        "重新开始",
+                        language.pick("回到标题", "Back to title"),
                         TextStyle {
                             font: font_assets.chs.clone(),
                             font_size: 30.0,
@@ -211,32 +537,31 @@ pub fn game_over_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
 pub fn click_button(
     interaction: Query<(&Interaction, &UIButton), Changed<Interaction>>,
     mut next_state: ResMut<NextState<AppState>>,
-    mut commands: Commands,
+    mut offers: ResMut<UpgradeOffers>,
+    mut ev_start: EventWriter<StartEvent>,
+    mut actions: UpgradeActions,
 ) {
     for (interaction, button) in &interaction {
-        match *interaction {
-            Interaction::Pressed => match button.0 {
-                "game_over:restart" => next_state.set(AppState::Start),
-                s if s.starts_with("tutorial:start") => commands.add(|world: &mut World| {
-                    world.send_event(StartEvent(!s.ends_with("-without-bgm")))
-                }),
-                s if s.starts_with("select_upgrade:") => {
-                    match s {
-                        "select_upgrade:move_speed" => commands
-                            .add(|world: &mut World| world.send_event(PlayerMoveSpeedUpEvent)),
-                        "select_upgrade:fire_rate" => commands
-                            .add(|world: &mut World| world.send_event(PlayerFireRateUpEvent)),
-                        "select_upgrade:bullet_speed" => commands
-                            .add(|world: &mut World| world.send_event(PlayerBulletSpeedUpEvent)),
-                        "select_upgrade:target_count" => commands
-                            .add(|world: &mut World| world.send_event(PlayerTargetCountUpEvent)),
-                        _ => {}
-                    }
-                    next_state.set(AppState::InGame)
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            UIButton::Restart => next_state.set(AppState::Start),
+            UIButton::Title => next_state.set(AppState::Title),
+            UIButton::Start => ev_start.send(StartEvent),
+            UIButton::SelectUpgrade(offer) if offers.banishing => {
+                actions.banish.send(BanishUpgradeEvent(offer.clone()))
+            }
+            UIButton::SelectUpgrade(offer) => {
+                actions.selected.send(UpgradeSelectedEvent(offer.clone()))
+            }
+            UIButton::SkipUpgrade => actions.skip.send(SkipUpgradeEvent),
+            UIButton::Reroll => actions.reroll.send(RerollUpgradesEvent),
+            UIButton::Banish => {
+                if offers.banishes > 0 || offers.banishing {
+                    offers.banishing = !offers.banishing;
                 }
-                _ => {}
-            },
-            _ => {}
+            }
         }
     }
 }
@@ -252,6 +577,229 @@ pub fn exit_select_upgrade_ui(
     entity.for_each(|e| commands.entity(e).despawn_recursive())
 }
 
+/// 暂停菜单, 主页面显示当前构筑, 设置页面与标题画面相同
+pub fn pause_menu_ui(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    sources: OfferSources,
+    menu: Res<PauseMenu>,
+    settings: Res<Settings>,
+    old: Query<Entity, With<PauseUI>>,
+) {
+    let language = settings.language;
+    for entity in &old {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::BLACK.with_a(0.6)),
+                ..Default::default()
+            },
+            PauseUI,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle::from_section(
+                    match *menu {
+                        PauseMenu::Main => language.pick("暂停", "Paused"),
+                        PauseMenu::Settings => language.pick("设置", "Settings"),
+                    },
+                    TextStyle {
+                        font: font_assets.chs.clone(),
+                        font_size: 50.0,
+                        ..Default::default()
+                    },
+                ),
+                Label,
+            ));
+            match *menu {
+                PauseMenu::Main => {
+                    for (label, entries) in sources.build_overview(language) {
+                        builder.spawn((
+                            TextBundle::from_section(
+                                format!("{}: {}", label, entries),
+                                TextStyle {
+                                    font: font_assets.chs.clone(),
+                                    font_size: 20.0,
+                                    ..Default::default()
+                                },
+                            )
+                            .with_style(Style {
+                                max_width: Val::Percent(80.),
+                                margin: UiRect::top(Val::Px(5f32)),
+                                ..Default::default()
+                            })
+                            .with_text_alignment(TextAlignment::Center),
+                            Label,
+                        ));
+                    }
+                    for (button, text) in [
+                        (PauseButton::Resume, language.pick("继续游戏", "Resume")),
+                        (PauseButton::Settings, language.pick("设置", "Settings")),
+                        (
+                            PauseButton::QuitToTitle,
+                            language.pick("回到标题", "Quit to title"),
+                        ),
+                    ] {
+                        spawn_text_button(
+                            builder,
+                            font_assets.chs.clone(),
+                            button,
+                            text.to_owned(),
+                            Color::WHITE,
+                        );
+                    }
+                }
+                PauseMenu::Settings => {
+                    spawn_settings_panel(builder, font_assets.chs.clone(), &settings);
+                    spawn_text_button(
+                        builder,
+                        font_assets.chs.clone(),
+                        PauseButton::Back,
+                        language.pick("返回", "Back").to_owned(),
+                        Color::WHITE,
+                    );
+                }
+            }
+        });
+}
+
+pub fn click_pause_button(
+    interaction: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut menu: ResMut<PauseMenu>,
+) {
+    for (interaction, button) in &interaction {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            PauseButton::Resume => next_state.set(AppState::InGame),
+            PauseButton::Settings => *menu = PauseMenu::Settings,
+            PauseButton::Back => *menu = PauseMenu::Main,
+            PauseButton::QuitToTitle => next_state.set(AppState::Title),
+        }
+    }
+}
+
+/// 标题画面和暂停菜单共用的设置页面, 每行一项设置
+pub fn spawn_settings_panel(builder: &mut ChildBuilder, font: Handle<Font>, settings: &Settings) {
+    let language = settings.language;
+    let switch = |on: bool| {
+        if on {
+            language.pick("开", "On")
+        } else {
+            language.pick("关", "Off")
+        }
+    };
+    let row = |builder: &mut ChildBuilder, spawn: &dyn Fn(&mut ChildBuilder)| {
+        builder
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|builder| spawn(builder));
+    };
+    let volumes: [(&str, f32, fn(f32) -> SettingsButton); 3] = [
+        (
+            language.pick("主音量", "Master volume"),
+            settings.master_volume,
+            SettingsButton::MasterVolume,
+        ),
+        (
+            language.pick("音乐", "Music"),
+            settings.music_volume,
+            SettingsButton::MusicVolume,
+        ),
+        (
+            language.pick("音效", "Sound effects"),
+            settings.sfx_volume,
+            SettingsButton::SfxVolume,
+        ),
+    ];
+    for (label, volume, button) in volumes {
+        row(builder, &|builder| {
+            builder.spawn(TextBundle::from_section(
+                format!("{}: {:.0}%", label, volume * 100f32),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.0,
+                    ..Default::default()
+                },
+            ));
+            for (change, text) in [(-0.1, "-"), (0.1, "+")] {
+                spawn_text_button(
+                    builder,
+                    font.clone(),
+                    button(change),
+                    text.to_owned(),
+                    Color::WHITE,
+                );
+            }
+        });
+    }
+    let toggles = [
+        (
+            SettingsButton::Fullscreen,
+            language.pick("全屏", "Fullscreen"),
+            switch(settings.fullscreen),
+        ),
+        (
+            SettingsButton::Vsync,
+            language.pick("垂直同步", "VSync"),
+            switch(settings.vsync),
+        ),
+        (
+            SettingsButton::ScreenShake,
+            language.pick("屏幕震动", "Screen shake"),
+            switch(settings.screen_shake),
+        ),
+        (
+            SettingsButton::DamageNumbers,
+            language.pick("伤害数字", "Damage numbers"),
+            switch(settings.damage_numbers),
+        ),
+        (
+            SettingsButton::Language,
+            language.pick("语言", "Language"),
+            language.name(),
+        ),
+    ];
+    row(builder, &|builder| {
+        for (button, label, value) in toggles {
+            spawn_text_button(
+                builder,
+                font.clone(),
+                button,
+                format!("{}: {}", label, value),
+                Color::WHITE,
+            );
+        }
+    });
+}
+
+pub fn exit_pause_ui(mut commands: Commands, entity: Query<Entity, With<PauseUI>>) {
+    entity.for_each(|e| commands.entity(e).despawn_recursive())
+}
+
+pub fn exit_stats_ui(mut commands: Commands, entity: Query<Entity, With<StatsUI>>) {
+    entity.for_each(|e| commands.entity(e).despawn_recursive())
+}
+
 pub fn show_properties(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
@@ -272,7 +820,8 @@ pub fn show_properties(
                 TextBundle::from_section(
                     "loading",
                     TextStyle {
-                        font: font_assets.eng.clone(),
+                        // 属性名称可能是中文
+                        font: font_assets.chs.clone(),
                         font_size: 15.0,
                         ..Default::default()
                     },
@@ -287,19 +836,26 @@ pub fn show_properties(
 pub fn update_properties(
     mut ev_props_update: EventReader<PropsUpdateEvent>,
     mut props: Query<&mut Text, With<PlayerProps>>,
-    move_speed: Query<&mut MoveSpeed, (With<Player>, Without<Bullet>)>,
-    rate: Query<&mut FireRate, With<Player>>,
-    bullet_speed: Query<&mut BulletSpeed, With<Player>>,
-    count: Query<&mut TargetCount, With<Player>>,
+    players: Query<(&Stats, &Health), With<Player>>,
+    settings: Res<Settings>,
 ) {
-    for _ in ev_props_update.read() {
-        let s = format!(
-            "MoveSpeed: {}\nFireRate: {}\nBulletSpeed: {}\nBulletCount: {}",
-            move_speed.single().0,
-            rate.single().0,
-            bullet_speed.single().0,
-            count.single().0,
+    // 暂停时切换了语言, 回到游戏后也要刷新
+    if ev_props_update.read().count() > 0 || settings.is_changed() {
+        let language = settings.language;
+        let (stats, health) = players.single();
+        let mut s = format!(
+            "{}: {:.0}/{}",
+            language.pick("生命", "Health"),
+            health.0.floor(),
+            stats.get(Stat::MaxHealth)
         );
+        // 未解锁的武器属性为 0, 不显示
+        for stat in Stat::ALL {
+            if stat == Stat::MaxHealth || stats.get(stat) == 0f32 {
+                continue;
+            }
+            s += &format!("\n{}: {}", stat.label(language), stats.get(stat));
+        }
 
         props.single_mut().sections[0].value = s;
     }
@@ -356,14 +912,75 @@ pub fn show_stats(
 }
 
 pub fn update_time_stats(
-    time: Res<Time>,
-    game_time: Res<GameTime>,
+    clock: Res<RunClock>,
     mut stat: Query<&mut Text, With<StatsUITime>>,
+    mut shown: Local<Option<u64>>,
 ) {
-    if time.elapsed_seconds() % 1.0 == 0f32 {
-        stat.single_mut().sections[0].value = format!(
-            "{:?}",
-            Duration::from_secs((time.elapsed() - game_time.0).as_secs())
-        );
+    let secs = clock.0.elapsed().as_secs();
+    if *shown != Some(secs) {
+        *shown = Some(secs);
+        stat.single_mut().sections[0].value = format!("{:?}", Duration::from_secs(secs));
+    }
+}
+
+pub fn show_weapon_strip(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10f32),
+                    width: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            GameEntity,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font_assets.chs.clone(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                ),
+                WeaponStrip,
+                Label,
+            ));
+        });
+}
+
+/// 按获得的顺序显示持有的武器和等级
+pub fn update_weapon_strip(
+    changed: Query<(), Or<(Changed<Weapon>, Changed<WeaponLevel>)>>,
+    inventory: Query<(&Weapon, &WeaponLevel)>,
+    weapons: WeaponDefs,
+    settings: Res<Settings>,
+    mut strip: Query<&mut Text, With<WeaponStrip>>,
+) {
+    if changed.is_empty() && !settings.is_changed() {
+        return;
+    }
+    let Some(weapons) = weapons.get() else {
+        return;
+    };
+    let mut owned: Vec<(&Weapon, &WeaponLevel)> = inventory.iter().collect();
+    owned.sort_by_key(|(weapon, _)| weapon.slot);
+    let mut sections: Vec<String> = owned
+        .into_iter()
+        .map(|(weapon, level)| {
+            let name = weapons.get(&weapon.id).map_or(weapon.id.as_str(), |def| {
+                def.localized_name(settings.language)
+            });
+            format!("{} Lv.{}", name, level.0)
+        })
+        .collect();
+    sections.resize(weapons.max_slots.max(sections.len()), "-".to_owned());
+    if let Ok(mut text) = strip.get_single_mut() {
+        text.sections[0].value = sections.join("  |  ");
     }
 }
@@ -0,0 +1,698 @@
+use std::fmt;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::assets::{ItemAssets, UpgradeAssets, WeaponAssets};
+use crate::components::{Level, Player, Weapon, WeaponLevel};
+use crate::events::{BanishUpgradeEvent, UpgradeActionEvents, UpgradeSelectedEvent};
+use crate::inventory::owned_weapons;
+use crate::items::{Items, OwnedItems};
+use crate::rng::RngStreams;
+use crate::settings::Language;
+use crate::states::AppState;
+use crate::stats::{Modifier, ModifierSource, Op, Stat, Stats};
+use crate::weapons::{Weapons, EVOLUTION_WEIGHT};
+
+const OFFER_COUNT: usize = 3;
+const REROLLS_PER_RUN: u32 = 3;
+const BANISHES_PER_RUN: u32 = 2;
+// 写入录像时武器, 进化和道具选项的编号前缀, 与普通升级区分开
+const WEAPON_OFFER_PREFIX: &str = "weapon:";
+const EVOLUTION_OFFER_PREFIX: &str = "evolve:";
+const ITEM_OFFER_PREFIX: &str = "item:";
+
+/// 升级画面中的一个选项
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Offer {
+    Upgrade(String),
+    Weapon(String),
+    // 进化配方的编号
+    Evolve(String),
+    Item(String),
+}
+
+impl Offer {
+    /// 从 `Display` 的结果中还原选项, 没有前缀的是普通升级
+    pub fn parse(s: &str) -> Offer {
+        if let Some(id) = s.strip_prefix(WEAPON_OFFER_PREFIX) {
+            Offer::Weapon(id.to_owned())
+        } else if let Some(id) = s.strip_prefix(EVOLUTION_OFFER_PREFIX) {
+            Offer::Evolve(id.to_owned())
+        } else if let Some(id) = s.strip_prefix(ITEM_OFFER_PREFIX) {
+            Offer::Item(id.to_owned())
+        } else {
+            Offer::Upgrade(s.to_owned())
+        }
+    }
+}
+
+impl fmt::Display for Offer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Offer::Upgrade(id) => write!(f, "{}", id),
+            Offer::Weapon(id) => write!(f, "{}{}", WEAPON_OFFER_PREFIX, id),
+            Offer::Evolve(id) => write!(f, "{}{}", EVOLUTION_OFFER_PREFIX, id),
+            Offer::Item(id) => write!(f, "{}{}", ITEM_OFFER_PREFIX, id),
+        }
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Upgrades {
+    #[serde(rename = "upgrade")]
+    pub upgrades: Vec<Upgrade>,
+}
+
+impl Upgrades {
+    pub fn get(&self, id: &str) -> Option<&Upgrade> {
+        self.upgrades.iter().find(|upgrade| upgrade.id == id)
+    }
+
+    /// 当前等级下可以选择的升级
+    pub fn available<'a>(
+        &'a self,
+        level: usize,
+        acquired: &'a AcquiredUpgrades,
+    ) -> impl Iterator<Item = &'a Upgrade> {
+        self.upgrades
+            .iter()
+            .filter(move |upgrade| upgrade.is_available(level, acquired))
+    }
+}
+
+/// 生成和描述升级选项需要的数据, 选项包括升级, 武器, 武器进化和被动道具
+#[derive(SystemParam)]
+pub struct OfferSources<'w, 's> {
+    upgrade_assets: Res<'w, UpgradeAssets>,
+    upgrades: Res<'w, Assets<Upgrades>>,
+    weapon_assets: Res<'w, WeaponAssets>,
+    weapons: Res<'w, Assets<Weapons>>,
+    item_assets: Res<'w, ItemAssets>,
+    items: Res<'w, Assets<Items>>,
+    player: Query<
+        'w,
+        's,
+        (
+            &'static Level,
+            &'static AcquiredUpgrades,
+            &'static OwnedItems,
+            &'static Stats,
+        ),
+        With<Player>,
+    >,
+    inventory: Query<'w, 's, (&'static Weapon, &'static WeaponLevel)>,
+}
+
+impl OfferSources<'_, '_> {
+    fn assets(&self) -> Option<(&Upgrades, &Weapons, &Items)> {
+        Some((
+            self.upgrades.get(&self.upgrade_assets.upgrades)?,
+            self.weapons.get(&self.weapon_assets.weapons)?,
+            self.items.get(&self.item_assets.items)?,
+        ))
+    }
+
+    /// 本次可以出现的选项以及它们的权重
+    pub fn pool(&self) -> Vec<(Offer, u32)> {
+        let (Some((upgrades, weapons, items)), Ok((level, acquired, owned_items, stats))) =
+            (self.assets(), self.player.get_single())
+        else {
+            return Vec::new();
+        };
+        let owned = owned_weapons(&self.inventory);
+        let luck = stats.get(Stat::Luck);
+        upgrades
+            .available(level.0, acquired)
+            .map(|upgrade| {
+                (
+                    Offer::Upgrade(upgrade.id.clone()),
+                    upgrade.rarity.weight_with_luck(luck),
+                )
+            })
+            .chain(
+                weapons
+                    .offers(&owned)
+                    .map(|(weapon, weight)| (Offer::Weapon(weapon.id.clone()), weight)),
+            )
+            .chain(
+                weapons
+                    .evolutions(&owned)
+                    .filter(|evolution| {
+                        acquired.stacks(&evolution.passive) > 0
+                            || owned_items.0.contains_key(&evolution.passive)
+                    })
+                    .map(|evolution| (Offer::Evolve(evolution.id.clone()), EVOLUTION_WEIGHT)),
+            )
+            .chain(items.offers(owned_items).map(|item| {
+                (
+                    Offer::Item(item.id.clone()),
+                    item.rarity.weight_with_luck(luck),
+                )
+            }))
+            .collect()
+    }
+
+    /// 选项的名字, 描述和边框颜色
+    pub fn describe(&self, offer: &Offer, language: Language) -> Option<(String, String, Color)> {
+        let (upgrades, weapons, items) = self.assets()?;
+        let (_, _, owned_items, _) = self.player.get_single().ok()?;
+        match offer {
+            Offer::Upgrade(id) => {
+                let upgrade = upgrades.get(id)?;
+                Some((
+                    upgrade.localized_name(language).to_owned(),
+                    upgrade.localized_description(language).to_owned(),
+                    upgrade.rarity.color(),
+                ))
+            }
+            Offer::Evolve(id) => {
+                let evolution = weapons.evolution(id)?;
+                let weapon_name = |id: &str| {
+                    weapons.get(id).map_or(id.to_owned(), |weapon| {
+                        weapon.localized_name(language).to_owned()
+                    })
+                };
+                let passive = upgrades
+                    .get(&evolution.passive)
+                    .map(|upgrade| upgrade.localized_name(language))
+                    .or_else(|| {
+                        items
+                            .get(&evolution.passive)
+                            .map(|item| item.localized_name(language))
+                    })
+                    .unwrap_or(&evolution.passive);
+                Some((
+                    format!(
+                        "{}: {}",
+                        language.pick("进化", "Evolve"),
+                        weapon_name(&evolution.into)
+                    ),
+                    format!("{} + {}", weapon_name(&evolution.weapon), passive),
+                    Rarity::Legendary.color(),
+                ))
+            }
+            Offer::Weapon(id) => {
+                let weapon = weapons.get(id)?;
+                let name = weapon.localized_name(language);
+                let owned = owned_weapons(&self.inventory);
+                Some(match owned.get(&weapon.id) {
+                    Some(level) => (
+                        format!("{} Lv.{}", name, level + 1),
+                        match language {
+                            Language::Chinese => format!("武器升级到 {} 级", level + 1),
+                            Language::English => format!("Weapon reaches level {}", level + 1),
+                        },
+                        weapon.color(),
+                    ),
+                    None => (
+                        name.to_owned(),
+                        language.pick("新武器", "New weapon").to_owned(),
+                        weapon.color(),
+                    ),
+                })
+            }
+            Offer::Item(id) => {
+                let item = items.get(id)?;
+                let name = item.localized_name(language);
+                let name = match owned_items.0.get(&item.id) {
+                    Some(level) => format!("{} Lv.{}", name, level + 1),
+                    None => name.to_owned(),
+                };
+                Some((
+                    name,
+                    item.localized_description(language).to_owned(),
+                    item.rarity.color(),
+                ))
+            }
+        }
+    }
+
+    /// 当前构筑的概览: 武器, 被动道具和已获得的升级, 每一类一行
+    pub fn build_overview(&self, language: Language) -> Vec<(&'static str, String)> {
+        let (Some((upgrades, weapons, items)), Ok((_, acquired, owned_items, _))) =
+            (self.assets(), self.player.get_single())
+        else {
+            return Vec::new();
+        };
+        let mut owned: Vec<_> = self.inventory.iter().collect();
+        owned.sort_by_key(|(weapon, _)| weapon.slot);
+        let weapons = owned
+            .into_iter()
+            .map(|(weapon, level)| {
+                let name = weapons
+                    .get(&weapon.id)
+                    .map_or(weapon.id.as_str(), |def| def.localized_name(language));
+                format!("{} Lv.{}", name, level.0)
+            })
+            .collect::<Vec<_>>();
+        let mut items = owned_items
+            .0
+            .iter()
+            .map(|(id, level)| {
+                let name = items
+                    .get(id)
+                    .map_or(id.as_str(), |item| item.localized_name(language));
+                format!("{} Lv.{}", name, level)
+            })
+            .collect::<Vec<_>>();
+        items.sort();
+        let mut upgrades = acquired
+            .0
+            .iter()
+            .map(|(id, stacks)| {
+                let name = upgrades
+                    .get(id)
+                    .map_or(id.as_str(), |upgrade| upgrade.localized_name(language));
+                format!("{} x{}", name, stacks)
+            })
+            .collect::<Vec<_>>();
+        upgrades.sort();
+        [
+            (language.pick("武器", "Weapons"), weapons),
+            (language.pick("道具", "Items"), items),
+            (language.pick("升级", "Upgrades"), upgrades),
+        ]
+        .into_iter()
+        .map(|(label, entries)| {
+            let entries = if entries.is_empty() {
+                "-".to_owned()
+            } else {
+                entries.join(", ")
+            };
+            (label, entries)
+        })
+        .collect()
+    }
+}
+
+/// 按权重不重复地抽取最多 `count` 个选项, 权重为 0 的选项不会出现
+pub fn roll_offers(
+    pool: &[(Offer, u32)],
+    excluded: &HashSet<Offer>,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Vec<Offer> {
+    // 去掉权重为 0 的选项, 保证总权重大于 0
+    let mut pool: Vec<&(Offer, u32)> = pool
+        .iter()
+        .filter(|(id, weight)| *weight > 0 && !excluded.contains(id))
+        .collect();
+    let mut offers = Vec::with_capacity(count);
+    while offers.len() < count && !pool.is_empty() {
+        let total: u32 = pool.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.gen_range(0..total);
+        let index = pool
+            .iter()
+            .position(|(_, weight)| {
+                if roll < *weight {
+                    return true;
+                }
+                roll -= weight;
+                false
+            })
+            .unwrap_or(0);
+        offers.push(pool.swap_remove(index).0.clone());
+    }
+    offers
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Upgrade {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    pub description: String,
+    #[serde(default)]
+    pub description_en: Option<String>,
+    #[serde(default)]
+    pub rarity: Rarity,
+    // 不填则可以无限叠加
+    pub max_stacks: Option<u32>,
+    // 需要先拥有的升级
+    #[serde(default)]
+    pub requires: Vec<String>,
+    // 只在等级为该数倍数时出现
+    pub every_n_levels: Option<usize>,
+    pub effects: Vec<UpgradeEffect>,
+}
+
+impl Upgrade {
+    pub fn localized_name(&self, language: Language) -> &str {
+        language.pick_data(&self.name, self.name_en.as_deref())
+    }
+
+    pub fn localized_description(&self, language: Language) -> &str {
+        language.pick_data(&self.description, self.description_en.as_deref())
+    }
+
+    pub fn is_available(&self, level: usize, acquired: &AcquiredUpgrades) -> bool {
+        let not_maxed = self
+            .max_stacks
+            .map_or(true, |max| acquired.stacks(&self.id) < max);
+        let requirements_met = self.requires.iter().all(|id| acquired.stacks(id) > 0);
+        let level_matches = self.every_n_levels.map_or(true, |n| level % n == 0);
+        not_maxed && requirements_met && level_matches
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    #[default]
+    Common,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub fn weight(&self) -> u32 {
+        match self {
+            Rarity::Common => 60,
+            Rarity::Rare => 25,
+            Rarity::Epic => 10,
+            Rarity::Legendary => 3,
+        }
+    }
+
+    /// 幸运提高稀有选项的权重
+    pub fn weight_with_luck(&self, luck: f32) -> u32 {
+        match self {
+            Rarity::Common => self.weight(),
+            _ => (self.weight() as f32 * luck).round() as u32,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Rarity::Common => Color::WHITE,
+            Rarity::Rare => Color::rgb(0.3, 0.6, 1.0),
+            Rarity::Epic => Color::rgb(0.7, 0.3, 1.0),
+            Rarity::Legendary => Color::GOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct UpgradeEffect {
+    pub stat: Stat,
+    pub op: Op,
+    pub value: f32,
+    // 不为空时是持续这么多秒的临时增益, 只对升级生效
+    #[serde(default)]
+    pub duration: Option<f32>,
+}
+
+#[derive(Component, Debug, Default)]
+pub struct AcquiredUpgrades(pub HashMap<String, u32>);
+
+impl AcquiredUpgrades {
+    pub fn stacks(&self, id: &str) -> u32 {
+        self.0.get(id).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct UpgradeOffers {
+    pub offers: Vec<Offer>,
+    pub rerolls: u32,
+    pub banishes: u32,
+    pub banished: HashSet<Offer>,
+    // 下一次点击升级时放逐而不是选择
+    pub banishing: bool,
+    // 已经升级但还没有选择的次数
+    pub pending: u32,
+}
+
+impl Default for UpgradeOffers {
+    fn default() -> Self {
+        UpgradeOffers {
+            offers: Vec::new(),
+            rerolls: REROLLS_PER_RUN,
+            banishes: BANISHES_PER_RUN,
+            banished: HashSet::new(),
+            banishing: false,
+            pending: 0,
+        }
+    }
+}
+
+pub fn roll_upgrade_offers(
+    mut offers: ResMut<UpgradeOffers>,
+    sources: OfferSources,
+    mut rng: ResMut<RngStreams>,
+) {
+    offers.banishing = false;
+    offers.offers = roll_offers(
+        &sources.pool(),
+        &offers.banished,
+        OFFER_COUNT,
+        &mut rng.upgrades,
+    );
+}
+
+pub fn handle_upgrade_offers(
+    mut events: UpgradeActionEvents,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut offers: ResMut<UpgradeOffers>,
+    sources: OfferSources,
+    mut rng: ResMut<RngStreams>,
+) {
+    let pool = sources.pool();
+    for _ in events.reroll.read() {
+        if offers.rerolls == 0 {
+            continue;
+        }
+        offers.rerolls -= 1;
+        offers.banishing = false;
+        offers.offers = roll_offers(&pool, &offers.banished, OFFER_COUNT, &mut rng.upgrades);
+    }
+    for BanishUpgradeEvent(offer) in events.banish.read() {
+        if offers.banishes == 0 {
+            continue;
+        }
+        let Some(index) = offers.offers.iter().position(|other| other == offer) else {
+            continue;
+        };
+        offers.banishes -= 1;
+        offers.banishing = false;
+        offers.banished.insert(offer.clone());
+        // 用一个新的升级补上被放逐的位置
+        let mut excluded = offers.banished.clone();
+        excluded.extend(offers.offers.iter().cloned());
+        match roll_offers(&pool, &excluded, 1, &mut rng.upgrades).pop() {
+            Some(replacement) => offers.offers[index] = replacement,
+            None => {
+                offers.offers.remove(index);
+            }
+        }
+    } // 选择或者跳过之后回到游戏, 选中的升级由 `apply_upgrade` 生效
+    let selected = events.selected.read().count() > 0;
+    let skipped = events.skip.read().count() > 0;
+    if selected || skipped {
+        offers.pending = offers.pending.saturating_sub(1);
+        next_state.set(AppState::InGame);
+    }
+}
+
+pub fn apply_upgrade(
+    mut ev_upgrade_selected: EventReader<UpgradeSelectedEvent>,
+    upgrade_assets: Res<UpgradeAssets>,
+    upgrades: Res<Assets<Upgrades>>,
+    mut players: Query<(&mut AcquiredUpgrades, &mut Stats), With<Player>>,
+) {
+    let Some(upgrades) = upgrades.get(&upgrade_assets.upgrades) else {
+        return;
+    };
+    for UpgradeSelectedEvent(offer) in ev_upgrade_selected.read() {
+        let Offer::Upgrade(id) = offer else {
+            continue;
+        };
+        let Some(upgrade) = upgrades.get(id) else {
+            continue;
+        };
+        let (mut acquired, mut stats) = players.single_mut();
+        *acquired.0.entry(upgrade.id.clone()).or_default() += 1;
+        // 再次选择同一个临时增益时重新计时, 不叠加
+        let buff = ModifierSource::Buff(upgrade.id.clone());
+        stats.remove_source(&buff);
+        for effect in &upgrade.effects {
+            let modifier = match effect.duration {
+                Some(seconds) => Modifier::new(effect.stat, effect.op, effect.value, buff.clone())
+                    .with_duration(seconds),
+                None => Modifier::new(
+                    effect.stat,
+                    effect.op,
+                    effect.value,
+                    ModifierSource::Upgrade(upgrade.id.clone()),
+                ),
+            };
+            stats.add_modifier(modifier);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn upgrade(id: &str, rarity: Rarity) -> Upgrade {
+        Upgrade {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            name_en: None,
+            description: String::new(),
+            description_en: None,
+            rarity,
+            max_stacks: None,
+            requires: Vec::new(),
+            every_n_levels: None,
+            effects: Vec::new(),
+        }
+    }
+
+    fn offers(ids: &[&str]) -> Vec<Offer> {
+        ids.iter()
+            .map(|id| Offer::Upgrade((*id).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn roll_skips_zero_weight_offers() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let pool = vec![
+            (Offer::Upgrade("a".to_owned()), 0),
+            (Offer::Upgrade("b".to_owned()), 0),
+        ];
+        assert!(roll_offers(&pool, &HashSet::new(), 3, &mut rng).is_empty());
+
+        let pool = vec![
+            (Offer::Upgrade("a".to_owned()), 0),
+            (Offer::Upgrade("b".to_owned()), 5),
+        ];
+        for _ in 0..100 {
+            assert_eq!(
+                roll_offers(&pool, &HashSet::new(), 3, &mut rng),
+                offers(&["b"])
+            );
+        }
+    }
+
+    #[test]
+    fn roll_excludes_banished_offers_without_duplicates() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let pool: Vec<(Offer, u32)> = offers(&["a", "b", "c", "d", "e"])
+            .into_iter()
+            .map(|offer| (offer, 10))
+            .collect();
+        let banished: HashSet<Offer> = offers(&["b", "d"]).into_iter().collect();
+        for _ in 0..100 {
+            let mut rolled = roll_offers(&pool, &banished, 3, &mut rng);
+            rolled.sort_by_key(|offer| offer.to_string());
+            assert_eq!(rolled, offers(&["a", "c", "e"]));
+        }
+        // 可选的不够时返回全部
+        assert_eq!(roll_offers(&pool, &banished, 10, &mut rng).len(), 3);
+    }
+
+    #[test]
+    fn unavailable_upgrades_are_not_offered() {
+        let mut maxed = upgrade("maxed", Rarity::Common);
+        maxed.max_stacks = Some(2);
+        let mut locked = upgrade("locked", Rarity::Common);
+        locked.requires = vec!["base".to_owned()];
+        let mut periodic = upgrade("periodic", Rarity::Common);
+        periodic.every_n_levels = Some(5);
+        let upgrades = Upgrades {
+            upgrades: vec![upgrade("base", Rarity::Common), maxed, locked, periodic],
+        };
+        let available = |level: usize, acquired: &AcquiredUpgrades| {
+            upgrades
+                .available(level, acquired)
+                .map(|upgrade| upgrade.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut acquired = AcquiredUpgrades::default();
+        assert_eq!(available(1, &acquired), ["base", "maxed"]);
+        assert_eq!(available(5, &acquired), ["base", "maxed", "periodic"]);
+        acquired.0.insert("maxed".to_owned(), 2);
+        acquired.0.insert("base".to_owned(), 1);
+        assert_eq!(available(1, &acquired), ["base", "locked"]);
+    }
+
+    #[test]
+    fn luck_raises_rare_weights() {
+        assert_eq!(
+            Rarity::Common.weight_with_luck(2.0),
+            Rarity::Common.weight()
+        );
+        assert_eq!(
+            Rarity::Rare.weight_with_luck(2.0),
+            Rarity::Rare.weight() * 2
+        );
+        assert_eq!(Rarity::Epic.weight_with_luck(0.0), 0);
+
+        // 稀有选项被抽到的比例跟随权重变化
+        let rare_share = |luck: f32| {
+            let mut rng = SmallRng::seed_from_u64(2);
+            let pool = vec![
+                (
+                    Offer::Upgrade("common".to_owned()),
+                    Rarity::Common.weight_with_luck(luck),
+                ),
+                (
+                    Offer::Upgrade("rare".to_owned()),
+                    Rarity::Rare.weight_with_luck(luck),
+                ),
+            ];
+            let rolls = 10_000;
+            let rare = (0..rolls)
+                .filter(|_| roll_offers(&pool, &HashSet::new(), 1, &mut rng) == offers(&["rare"]))
+                .count();
+            rare as f32 / rolls as f32
+        };
+        assert!((rare_share(1.0) - 25.0 / 85.0).abs() < 0.02);
+        assert!((rare_share(2.0) - 50.0 / 110.0).abs() < 0.02);
+        assert_eq!(rare_share(0.0), 0.0);
+    }
+
+    #[test]
+    fn default_data_has_english_text() {
+        let upgrades: Upgrades =
+            toml::from_str(include_str!("../assets/data/default.upgrades.toml")).unwrap();
+        let items: Items =
+            toml::from_str(include_str!("../assets/data/default.items.toml")).unwrap();
+        let texts = upgrades
+            .upgrades
+            .iter()
+            .map(|upgrade| (&upgrade.id, &upgrade.name_en, &upgrade.description_en))
+            .chain(
+                items
+                    .items
+                    .iter()
+                    .map(|item| (&item.id, &item.name_en, &item.description_en)),
+            );
+        for (id, name, description) in texts {
+            assert!(name.is_some() && description.is_some(), "`{}`", id);
+        }
+
+        // 没有英文时使用中文
+        let mut upgrade = upgrades.upgrades[0].clone();
+        assert_eq!(upgrade.localized_name(Language::Chinese), upgrade.name);
+        assert_eq!(
+            Some(upgrade.localized_name(Language::English)),
+            upgrade.name_en.as_deref()
+        );
+        upgrade.name_en = None;
+        assert_eq!(upgrade.localized_name(Language::English), upgrade.name);
+    }
+}
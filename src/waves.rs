@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Waves {
+    #[serde(rename = "wave")]
+    pub waves: Vec<Wave>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Wave {
+    // 从游戏开始后的第几秒开始
+    pub start: f32,
+    // 持续秒数, 不填则一直持续
+    pub duration: Option<f32>,
+    // 敌人id -> 权重
+    pub mix: BTreeMap<String, u32>,
+    // 每秒生成的敌人数量
+    pub spawn_rate: f32,
+    pub max_alive: usize,
+}
+
+impl Wave {
+    /// 按照权重随机挑选一种敌人
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<&str> {
+        let total: u32 = self.mix.values().sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0..total);
+        for (id, weight) in &self.mix {
+            if roll < *weight {
+                return Some(id);
+            }
+            roll -= weight;
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct WaveDirector {
+    wave: Option<usize>,
+    wave_timer: Option<Timer>,
+    spawn_timer: Timer,
+}
+
+impl WaveDirector {
+    pub fn current<'a>(&self, waves: &'a Waves) -> Option<&'a Wave> {
+        self.wave.and_then(|index| waves.waves.get(index))
+    }
+
+    /// 按本局时长推进计时器, 返回这一帧应该生成的敌人数量
+    pub fn tick(&mut self, delta: Duration, elapsed: Duration, waves: &Waves) -> u32 {
+        let elapsed = elapsed.as_secs_f32();
+        while let Some(next) = waves.waves.get(self.wave.map_or(0, |index| index + 1)) {
+            if next.start > elapsed {
+                break;
+            }
+            self.wave = Some(self.wave.map_or(0, |index| index + 1));
+            self.wave_timer = next
+                .duration
+                .map(|duration| Timer::from_seconds(duration, TimerMode::Once));
+            self.spawn_timer =
+                Timer::from_seconds(1f32 / next.spawn_rate.max(0.001), TimerMode::Repeating);
+        }
+
+        if self.wave.is_none() {
+            return 0;
+        }
+        if let Some(timer) = &mut self.wave_timer {
+            if timer.tick(delta).finished() {
+                return 0;
+            }
+        }
+        self.spawn_timer.tick(delta).times_finished_this_tick()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave(start: f32, duration: Option<f32>, spawn_rate: f32) -> Wave {
+        Wave {
+            start,
+            duration,
+            mix: BTreeMap::from([("grunt".to_owned(), 1)]),
+            spawn_rate,
+            max_alive: 100,
+        }
+    }
+
+    /// 推进 `millis` 毫秒, 返回这一帧生成的数量
+    fn advance(
+        director: &mut WaveDirector,
+        waves: &Waves,
+        elapsed: &mut Duration,
+        millis: u64,
+    ) -> u32 {
+        let delta = Duration::from_millis(millis);
+        *elapsed += delta;
+        director.tick(delta, *elapsed, waves)
+    }
+
+    #[test]
+    fn waits_for_first_wave() {
+        let waves = Waves {
+            waves: vec![wave(2.0, None, 1.0)],
+        };
+        let (mut director, mut elapsed) = (WaveDirector::default(), Duration::ZERO);
+        assert_eq!(advance(&mut director, &waves, &mut elapsed, 1000), 0);
+        assert!(director.current(&waves).is_none());
+        assert_eq!(advance(&mut director, &waves, &mut elapsed, 1000), 1);
+        assert_eq!(director.current(&waves).unwrap().start, 2.0);
+    }
+
+    #[test]
+    fn resets_spawn_timer_on_next_wave() {
+        let waves = Waves {
+            waves: vec![wave(0.0, None, 1.0), wave(3.0, None, 2.0)],
+        };
+        let (mut director, mut elapsed) = (WaveDirector::default(), Duration::ZERO);
+        let counts: Vec<u32> = [900, 900, 900]
+            .into_iter()
+            .map(|millis| advance(&mut director, &waves, &mut elapsed, millis))
+            .collect();
+        assert_eq!(counts, [0, 1, 1]);
+        // 上一波剩下的进度不会带到新的一波
+        assert_eq!(advance(&mut director, &waves, &mut elapsed, 300), 0);
+        assert_eq!(director.current(&waves).unwrap().start, 3.0);
+        assert_eq!(advance(&mut director, &waves, &mut elapsed, 200), 1);
+    }
+
+    #[test]
+    fn no_spawns_between_waves() {
+        let waves = Waves {
+            waves: vec![wave(0.0, Some(2.0), 1.0), wave(5.0, None, 1.0)],
+        };
+        let (mut director, mut elapsed) = (WaveDirector::default(), Duration::ZERO);
+        let counts: Vec<u32> = (0..6)
+            .map(|_| advance(&mut director, &waves, &mut elapsed, 1000))
+            .collect();
+        assert_eq!(counts, [1, 0, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn skips_waves_that_already_ended() {
+        let waves = Waves {
+            waves: vec![
+                wave(0.0, None, 1.0),
+                wave(1.0, None, 1.0),
+                wave(2.0, None, 4.0),
+            ],
+        };
+        let (mut director, mut elapsed) = (WaveDirector::default(), Duration::ZERO);
+        assert_eq!(advance(&mut director, &waves, &mut elapsed, 5000), 20);
+        assert_eq!(director.current(&waves).unwrap().start, 2.0);
+    }
+}
@@ -0,0 +1,284 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::assets::WeaponAssets;
+use crate::settings::Language;
+
+// 升级选项中已有武器升级和新武器的权重, 与升级稀有度的权重比较
+const LEVEL_UP_WEIGHT: u32 = 40;
+const NEW_WEAPON_WEIGHT: u32 = 25;
+pub const EVOLUTION_WEIGHT: u32 = 100;
+
+/// 加载好的武器定义
+#[derive(SystemParam)]
+pub struct WeaponDefs<'w> {
+    assets: Res<'w, WeaponAssets>,
+    weapons: Res<'w, Assets<Weapons>>,
+}
+
+impl WeaponDefs<'_> {
+    pub fn get(&self) -> Option<&Weapons> {
+        self.weapons.get(&self.assets.weapons)
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Weapons {
+    pub starting: String,
+    // 最多同时持有的武器数量
+    #[serde(default = "default_max_slots")]
+    pub max_slots: usize,
+    #[serde(rename = "weapon")]
+    pub weapons: Vec<WeaponDef>,
+    #[serde(default, rename = "evolution")]
+    pub evolutions: Vec<Evolution>,
+    pub orbit: OrbitDef,
+    pub aura: AuraDef,
+    pub shockwave: ShockwaveDef,
+}
+
+impl Weapons {
+    pub fn get(&self, id: &str) -> Option<&WeaponDef> {
+        self.weapons.iter().find(|weapon| weapon.id == id)
+    }
+
+    /// 可以出现在升级选项中的武器和它们的权重, 已有的武器提供升级, 还有空位时提供新武器
+    pub fn offers<'a>(
+        &'a self,
+        owned: &'a HashMap<String, u32>,
+    ) -> impl Iterator<Item = (&'a WeaponDef, u32)> {
+        let slots_left = owned.len() < self.max_slots;
+        self.weapons
+            .iter()
+            .filter_map(move |weapon| match owned.get(&weapon.id) {
+                Some(level) if *level < weapon.max_level => Some((weapon, LEVEL_UP_WEIGHT)),
+                // 进化后的武器只能通过进化获得, 已经进化的武器也不会再出现
+                None if slots_left && !weapon.evolved && !self.is_evolved(&weapon.id, owned) => {
+                    Some((weapon, NEW_WEAPON_WEIGHT))
+                }
+                _ => None,
+            })
+    }
+
+    pub fn evolution(&self, id: &str) -> Option<&Evolution> {
+        self.evolutions.iter().find(|evolution| evolution.id == id)
+    }
+
+    /// 武器已经满级的进化配方, 还需要检查是否拥有对应的被动道具或升级
+    pub fn evolutions<'a>(
+        &'a self,
+        owned: &'a HashMap<String, u32>,
+    ) -> impl Iterator<Item = &'a Evolution> {
+        self.evolutions.iter().filter(move |evolution| {
+            let maxed = match (owned.get(&evolution.weapon), self.get(&evolution.weapon)) {
+                (Some(level), Some(weapon)) => *level >= weapon.max_level,
+                _ => false,
+            };
+            maxed && !owned.contains_key(&evolution.into)
+        })
+    }
+
+    fn is_evolved(&self, id: &str, owned: &HashMap<String, u32>) -> bool {
+        self.evolutions
+            .iter()
+            .any(|evolution| evolution.weapon == id && owned.contains_key(&evolution.into))
+    }
+}
+
+/// 武器满级并且拥有指定的被动道具或升级后, 可以进化成另一把武器
+#[derive(Debug, Clone, Deserialize)]
+pub struct Evolution {
+    pub id: String,
+    pub weapon: String,
+    // 需要拥有的被动道具或升级
+    pub passive: String,
+    pub into: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeaponDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    pub shape: ProjectileShape,
+    pub radius: f32,
+    #[serde(default = "default_color")]
+    pub color: String,
+    pub speed: f32,
+    pub damage: f32,
+    // 秒
+    pub cooldown: f32,
+    pub count: usize,
+    pub targeting: Targeting,
+    // 子弹最多存在的秒数
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32,
+    // 子弹最远的飞行距离
+    #[serde(default = "default_range")]
+    pub range: f32,
+    #[serde(default)]
+    pub retarget: bool,
+    #[serde(default)]
+    pub projectile: ProjectileDef,
+    #[serde(default = "default_max_level")]
+    pub max_level: u32,
+    #[serde(default)]
+    pub growth: WeaponGrowth,
+    // 只能通过进化获得
+    #[serde(default)]
+    pub evolved: bool,
+}
+
+impl WeaponDef {
+    pub fn localized_name(&self, language: Language) -> &str {
+        language.pick_data(&self.name, self.name_en.as_deref())
+    }
+
+    pub fn color(&self) -> Color {
+        hex_color(&self.color)
+    }
+
+    /// 每分钟开火次数, 子弹速度, 伤害和数量
+    pub fn stats_at(&self, level: u32) -> (f32, f32, f32, usize) {
+        let ups = level.saturating_sub(1);
+        let fire_rate = 60f32 / self.cooldown * (1f32 + self.growth.fire_rate * ups as f32);
+        let damage = self.damage * (1f32 + self.growth.damage * ups as f32);
+        let count = match self.growth.count_every {
+            0 => self.count,
+            every => self.count + (ups / every) as usize,
+        };
+        (fire_rate, self.speed, damage, count)
+    }
+}
+
+fn default_max_level() -> u32 {
+    8
+}
+
+fn default_max_slots() -> usize {
+    4
+}
+
+fn hex_color(hex: &str) -> Color {
+    Color::hex(hex).unwrap_or(Color::WHITE)
+}
+
+fn default_color() -> String {
+    "ffffff".to_owned()
+}
+
+fn default_lifetime() -> f32 {
+    3.0
+}
+
+fn default_range() -> f32 {
+    800.0
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectileShape {
+    Circle,
+    Square,
+    Triangle,
+}
+
+/// 子弹的飞行和命中方式, 不填则直线飞行, 命中一次后消失
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectileDef {
+    // 每秒最多转向的弧度, 0 为直线飞行
+    pub homing: f32,
+    // 可以额外穿透的敌人数量
+    pub pierce: u32,
+    // 命中后弹向附近敌人的次数
+    pub ricochet: u32,
+    pub ricochet_range: f32,
+    // 消失时分裂出的子弹数量, 分裂出的子弹直线飞行
+    pub split: u32,
+    // 分裂子弹的伤害占原伤害的比例
+    pub split_damage: f32,
+}
+
+/// 武器每升一级的成长
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct WeaponGrowth {
+    // 每级增加的伤害比例
+    pub damage: f32,
+    // 每级增加的开火速率比例
+    pub fire_rate: f32,
+    // 每隔几级子弹数量 +1, 0 为不增加
+    pub count_every: u32,
+}
+
+/// 围绕玩家旋转的刀刃, 数量和伤害由 `Stat::OrbitCount` 和 `Stat::OrbitDamage` 决定
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrbitDef {
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    #[serde(default = "default_color")]
+    pub color: String,
+    // 刀刃到玩家的距离
+    pub distance: f32,
+    pub size: f32,
+    // 每秒旋转的弧度
+    pub spin: f32,
+    // 同一个敌人两次受到刀刃伤害的最短间隔, 秒
+    pub hit_interval: f32,
+}
+
+/// 持续伤害玩家周围敌人的光环, 范围和每秒伤害由 `Stat::AuraRadius` 和 `Stat::AuraDamage` 决定
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuraDef {
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    #[serde(default = "default_color")]
+    pub color: String,
+    // 结算伤害的间隔, 秒
+    pub tick: f32,
+}
+
+/// 周期性从玩家向外扩散的冲击波, 频率, 伤害和范围由 `Stat::Shockwave*` 决定
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShockwaveDef {
+    pub name: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    #[serde(default = "default_color")]
+    pub color: String,
+    // 扩散到最大范围所用的秒数
+    pub duration: f32,
+    pub thickness: f32,
+}
+
+impl OrbitDef {
+    pub fn color(&self) -> Color {
+        hex_color(&self.color)
+    }
+}
+
+impl AuraDef {
+    pub fn color(&self) -> Color {
+        hex_color(&self.color)
+    }
+}
+
+impl ShockwaveDef {
+    pub fn color(&self) -> Color {
+        hex_color(&self.color)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Targeting {
+    Nearest,
+    Farthest,
+    Random,
+}